@@ -0,0 +1,349 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::fmt;
+use blockid::SYS_BLOCK;
+use mountinfo::MountInfo;
+use mounts::Mount;
+use sysfs::{list_dir, read_attr, read_num};
+
+/// /sys/block/<dev>/size is always in 512 byte sectors
+static SYSFS_SECTOR: u64 = 512;
+
+/// A disk, partition or stacked device (dm, md) from /sys/block
+#[derive(Debug)]
+pub struct BlockDevice {
+    name: String,
+    major: u32,
+    minor: u32,
+    size: u64,
+    logical_block_size: u64,
+    physical_block_size: u64,
+    rotational: bool,
+    removable: bool,
+    scheduler: Option<String>,
+    read_ahead_kb: u64,
+    parent: Option<String>,
+    partitions: Vec<String>,
+    holders: Vec<String>,
+    slaves: Vec<String>,
+}
+
+impl PartialEq for BlockDevice {
+    fn eq(&self, other: &BlockDevice) -> bool {
+        (self.name == other.name) & (self.major == other.major) & (self.minor == other.minor)
+            & (self.size == other.size) & (self.logical_block_size == other.logical_block_size)
+            & (self.physical_block_size == other.physical_block_size)
+            & (self.rotational == other.rotational) & (self.removable == other.removable)
+            & (self.scheduler == other.scheduler) & (self.read_ahead_kb == other.read_ahead_kb)
+            & (self.parent == other.parent) & (self.partitions == other.partitions)
+            & (self.holders == other.holders) & (self.slaves == other.slaves)
+    }
+}
+
+impl fmt::Display for BlockDevice {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "Device: {} ({}:{}) Size: {} {}",
+            self.name,
+            self.major,
+            self.minor,
+            self.size,
+            if self.rotational { "HDD" } else { "SSD" }
+        )
+    }
+}
+
+impl BlockDevice {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_major(&self) -> u32 {
+        self.major
+    }
+
+    pub fn get_minor(&self) -> u32 {
+        self.minor
+    }
+
+    /// Size in bytes
+    pub fn get_size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn get_logical_block_size(&self) -> u64 {
+        self.logical_block_size
+    }
+
+    pub fn get_physical_block_size(&self) -> u64 {
+        self.physical_block_size
+    }
+
+    /// Spinning disk, partitions report their disk's value
+    pub fn is_rotational(&self) -> bool {
+        self.rotational
+    }
+
+    pub fn is_removable(&self) -> bool {
+        self.removable
+    }
+
+    /// Active I/O scheduler, None for devices without a queue such as partitions
+    pub fn get_scheduler(&self) -> Option<&str> {
+        self.scheduler.as_deref()
+    }
+
+    pub fn get_read_ahead_kb(&self) -> u64 {
+        self.read_ahead_kb
+    }
+
+    /// Disk a partition belongs to
+    pub fn get_parent(&self) -> Option<&str> {
+        self.parent.as_deref()
+    }
+
+    pub fn get_partitions(&self) -> &Vec<String> {
+        &self.partitions
+    }
+
+    /// Devices stacked on top of this one, e.g. the dm device of an LVM volume
+    pub fn get_holders(&self) -> &Vec<String> {
+        &self.holders
+    }
+
+    /// Devices this one is stacked on, e.g. the members of an md array
+    pub fn get_slaves(&self) -> &Vec<String> {
+        &self.slaves
+    }
+
+    pub fn is_partition(&self) -> bool {
+        self.parent.is_some()
+    }
+
+    fn read(dir: &Path, parent: Option<&BlockDevice>) -> Result<BlockDevice, io::Error> {
+        let name = dir.file_name().unwrap().to_string_lossy().into_owned();
+        let dev = read_attr(dir, "dev")?;
+        let (major, minor) = match dev.find(':') {
+            Some(i) => (
+                dev[..i].parse::<u32>().unwrap_or(0),
+                dev[i + 1..].parse::<u32>().unwrap_or(0),
+            ),
+            None => (0, 0),
+        };
+        let queue = dir.join("queue");
+        let mut device = BlockDevice {
+            name,
+            major,
+            minor,
+            size: read_num(dir, "size") * SYSFS_SECTOR,
+            logical_block_size: read_num(&queue, "logical_block_size"),
+            physical_block_size: read_num(&queue, "physical_block_size"),
+            rotational: read_num(&queue, "rotational") == 1,
+            removable: read_num(dir, "removable") == 1,
+            scheduler: read_attr(&queue, "scheduler")
+                .ok()
+                .and_then(|s| active_scheduler(&s)),
+            read_ahead_kb: read_num(&queue, "read_ahead_kb"),
+            parent: parent.map(|p| p.name.clone()),
+            partitions: vec![],
+            holders: list_dir(&dir.join("holders")),
+            slaves: list_dir(&dir.join("slaves")),
+        };
+        if let Some(p) = parent {
+            device.logical_block_size = p.logical_block_size;
+            device.physical_block_size = p.physical_block_size;
+            device.rotational = p.rotational;
+            device.removable = p.removable;
+            device.read_ahead_kb = p.read_ahead_kb;
+        }
+        Ok(device)
+    }
+}
+
+#[derive(Debug)]
+pub struct BlockDevices {
+    devices: Vec<BlockDevice>,
+}
+
+impl BlockDevices {
+    pub fn get_devices(self) -> Vec<BlockDevice> {
+        self.devices
+    }
+
+    pub fn new() -> BlockDevices {
+        BlockDevices::read(Path::new(SYS_BLOCK)).unwrap()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&BlockDevice> {
+        self.devices.iter().find(|d| d.name == name)
+    }
+
+    pub fn get_by_number(&self, major: u32, minor: u32) -> Option<&BlockDevice> {
+        self.devices
+            .iter()
+            .find(|d| d.major == major && d.minor == minor)
+    }
+
+    /// Whole disks at the bottom of the stack under a device, walking through partitions,
+    /// device-mapper (LVM, dm-crypt) and md RAID
+    pub fn physical_disks(&self, name: &str) -> Vec<&BlockDevice> {
+        let mut disks: Vec<&BlockDevice> = vec![];
+        let mut todo = vec![name.to_string()];
+        while let Some(n) = todo.pop() {
+            let dev = match self.get(&n) {
+                Some(d) => d,
+                None => continue,
+            };
+            if !dev.slaves.is_empty() {
+                todo.extend(dev.slaves.iter().cloned());
+                continue;
+            }
+            let disk = match dev.parent {
+                Some(ref p) => self.get(p).unwrap_or(dev),
+                None => dev,
+            };
+            if !disks.iter().any(|d| d.name == disk.name) {
+                disks.push(disk)
+            }
+        }
+        disks
+    }
+
+    /// The device and everything stacked beneath it, e.g. dm-0, md0 and the md members
+    /// for LVM on software RAID. A partition's disk counts as beneath it, so md0p1 leads
+    /// to md0
+    pub fn lower_devices(&self, name: &str) -> Vec<&BlockDevice> {
+        let mut lower: Vec<&BlockDevice> = vec![];
+        let mut todo = vec![name.to_string()];
+        while let Some(n) = todo.pop() {
+            if let Some(dev) = self.get(&n) {
+                if !lower.iter().any(|d| d.name == dev.name) {
+                    todo.extend(dev.slaves.iter().cloned());
+                    todo.extend(dev.parent.iter().cloned());
+                    lower.push(dev);
+                }
+            }
+        }
+        lower
+    }
+
+    /// Physical disks backing a mounted filesystem
+    pub fn for_mount(&self, mount: &Mount) -> Vec<&BlockDevice> {
+        match mount.device_number() {
+            Ok(dev) => self.physical_disks_of(dev),
+            Err(_) => vec![],
+        }
+    }
+
+    /// for_mount with mountinfo that was already read, for looking up many mounts
+    pub fn for_mount_in(&self, mount: &Mount, info: &MountInfo) -> Vec<&BlockDevice> {
+        match mount.device_number_in(info) {
+            Ok(dev) => self.physical_disks_of(dev),
+            Err(_) => vec![],
+        }
+    }
+
+    fn physical_disks_of(&self, (major, minor): (u32, u32)) -> Vec<&BlockDevice> {
+        match self.get_by_number(major, minor) {
+            Some(d) => self.physical_disks(&d.name),
+            None => vec![],
+        }
+    }
+
+    /// Devices removed while the scan is running, e.g. an unplugged USB disk or a dm
+    /// table being torn down, are skipped
+    pub(crate) fn read(sys_block: &Path) -> Result<BlockDevices, io::Error> {
+        let mut devices = vec![];
+        for entry in fs::read_dir(sys_block)? {
+            let dir = entry?.path();
+            let mut disk = match skip_removed(BlockDevice::read(&dir, None))? {
+                Some(d) => d,
+                None => continue,
+            };
+            let entries = match skip_removed(fs::read_dir(&dir))? {
+                Some(e) => e,
+                None => continue,
+            };
+            let mut parts = vec![];
+            for sub in entries {
+                let sub = sub?.path();
+                if sub.join("partition").exists() {
+                    if let Some(p) = skip_removed(BlockDevice::read(&sub, Some(&disk)))? {
+                        parts.push(p);
+                    }
+                }
+            }
+            parts.sort_by_key(|p| p.minor);
+            disk.partitions = parts.iter().map(|p| p.name.clone()).collect();
+            devices.push(disk);
+            devices.extend(parts);
+        }
+        Ok(BlockDevices { devices })
+    }
+}
+
+fn skip_removed<T>(result: Result<T, io::Error>) -> Result<Option<T>, io::Error> {
+    match result {
+        Ok(t) => Ok(Some(t)),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// The scheduler file lists every available scheduler with the active one in brackets
+fn active_scheduler(s: &str) -> Option<String> {
+    s.split_whitespace()
+        .find(|s| s.starts_with('['))
+        .map(|s| s.trim_matches(|c| c == '[' || c == ']').to_string())
+}
+
+#[test]
+fn test_block_devices_read() {
+    use testtree::TestTree;
+
+    let tree = TestTree::new("block");
+    tree.write_all(&[
+        ("sda/dev", "8:0"),
+        ("sda/size", "1953525168"),
+        ("sda/removable", "0"),
+        ("sda/queue/logical_block_size", "512"),
+        ("sda/queue/physical_block_size", "4096"),
+        ("sda/queue/rotational", "0"),
+        ("sda/queue/scheduler", "none [mq-deadline] kyber bfq"),
+        ("sda/queue/read_ahead_kb", "128"),
+        ("sda/sda1/dev", "8:1"),
+        ("sda/sda1/size", "2048"),
+        ("sda/sda1/partition", "1"),
+        ("sda/sda2/dev", "8:2"),
+        ("sda/sda2/size", "1953521664"),
+        ("sda/sda2/partition", "2"),
+        ("sdb/dev", "8:16"),
+        ("sdb/size", "1953525168"),
+        ("sdb/queue/rotational", "1"),
+        ("dm-0/dev", "253:0"),
+        ("dm-0/size", "3907043328"),
+        ("dm-0/queue/rotational", "0"),
+    ]);
+    for dir in &["sda/sda2/holders/dm-0", "sdb/holders/dm-0", "dm-0/slaves/sda2", "dm-0/slaves/sdb"] {
+        tree.mkdir(dir);
+    }
+    // a device unplugged while the scan runs leaves a dangling link behind
+    tree.symlink("../devices/usb1/sdc", "sdc");
+
+    let devs = BlockDevices::read(tree.path()).unwrap();
+    assert!(devs.get("sdc").is_none());
+    let sda = devs.get("sda").unwrap();
+    assert_eq!(sda.get_size(), 1000204886016);
+    assert_eq!(sda.get_physical_block_size(), 4096);
+    assert_eq!(sda.get_scheduler(), Some("mq-deadline"));
+    assert_eq!(sda.get_partitions(), &vec!["sda1", "sda2"]);
+    let sda2 = devs.get_by_number(8, 2).unwrap();
+    assert_eq!(sda2.get_parent(), Some("sda"));
+    assert_eq!(sda2.get_holders(), &vec!["dm-0"]);
+    assert_eq!(sda2.get_read_ahead_kb(), 128);
+    let disks: Vec<&str> = devs.physical_disks("dm-0").iter().map(|d| d.get_name()).collect();
+    assert_eq!(disks.len(), 2);
+    assert!(disks.contains(&"sda") && disks.contains(&"sdb"));
+}
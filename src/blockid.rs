@@ -0,0 +1,248 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str;
+
+pub static DISK_BY: &str = "/dev/disk";
+pub static SYS_BLOCK: &str = "/sys/block";
+
+/// The /dev/disk/by-* directories udev maintains and the tags they map to
+static TAGS: &[(&str, &str)] = &[
+    ("UUID", "by-uuid"),
+    ("LABEL", "by-label"),
+    ("PARTUUID", "by-partuuid"),
+    ("PARTLABEL", "by-partlabel"),
+];
+
+#[derive(Debug)]
+pub enum BlockIdentityError {
+    /// The device or tag doesn't resolve to a block device
+    NotFound(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for BlockIdentityError {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BlockIdentityError::NotFound(ref d) => write!(fmtr, "No block device found for {}", d),
+            BlockIdentityError::Io(ref e) => write!(fmtr, "{}", e),
+        }
+    }
+}
+
+impl Error for BlockIdentityError {}
+
+impl From<io::Error> for BlockIdentityError {
+    fn from(e: io::Error) -> BlockIdentityError {
+        BlockIdentityError::Io(e)
+    }
+}
+
+/// Identifiers udev knows a block device by
+#[derive(Debug)]
+pub struct BlockIdentity {
+    device: PathBuf,
+    dm_name: Option<String>,
+    uuid: Option<String>,
+    label: Option<String>,
+    partuuid: Option<String>,
+    partlabel: Option<String>,
+}
+
+impl PartialEq for BlockIdentity {
+    fn eq(&self, other: &BlockIdentity) -> bool {
+        (self.device == other.device) & (self.dm_name == other.dm_name)
+            & (self.uuid == other.uuid) & (self.label == other.label)
+            & (self.partuuid == other.partuuid) & (self.partlabel == other.partlabel)
+    }
+}
+
+impl fmt::Display for BlockIdentity {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "Device: {} UUID: {} Label: {}",
+            self.device.display(),
+            self.uuid.as_ref().map_or("-", |u| u.as_str()),
+            self.label.as_ref().map_or("-", |l| l.as_str())
+        )
+    }
+}
+
+impl BlockIdentity {
+    /// Canonical device node, e.g. /dev/dm-0 for /dev/mapper/vg-root
+    pub fn get_device(&self) -> &Path {
+        &self.device
+    }
+
+    /// Device-mapper name for dm-* devices, e.g. "vg-root"
+    pub fn get_dm_name(&self) -> Option<&str> {
+        self.dm_name.as_deref()
+    }
+
+    /// Filesystem UUID
+    pub fn get_uuid(&self) -> Option<&str> {
+        self.uuid.as_deref()
+    }
+
+    /// Filesystem label
+    pub fn get_label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// GPT partition UUID
+    pub fn get_partuuid(&self) -> Option<&str> {
+        self.partuuid.as_deref()
+    }
+
+    /// GPT partition name
+    pub fn get_partlabel(&self) -> Option<&str> {
+        self.partlabel.as_deref()
+    }
+
+    /// Resolve a device path such as /dev/sda1 or /dev/mapper/vg-root, or a tag such as
+    /// UUID=... LABEL=... PARTUUID=... PARTLABEL=... as found in fstab
+    pub fn resolve(dev: &str) -> Result<BlockIdentity, BlockIdentityError> {
+        BlockIdentity::resolve_in(Path::new("/"), dev)
+    }
+
+    /// Resolve against a different root, used for chroots and tests
+    pub(crate) fn resolve_in(root: &Path, dev: &str) -> Result<BlockIdentity, BlockIdentityError> {
+        let root = &fs::canonicalize(root)?;
+        let disk_by = root.join(DISK_BY.trim_start_matches('/'));
+        let path = match dev.find('=') {
+            Some(i) => {
+                let dir = TAGS.iter()
+                    .find(|t| t.0 == &dev[..i])
+                    .map(|t| t.1)
+                    .ok_or_else(|| BlockIdentityError::NotFound(dev.to_string()))?;
+                disk_by.join(dir).join(escape_tag(&dev[i + 1..]))
+            }
+            None => root.join(dev.trim_start_matches('/')),
+        };
+        let device = match fs::canonicalize(&path) {
+            Ok(d) => d,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(BlockIdentityError::NotFound(dev.to_string()))
+            }
+            Err(e) => return Err(BlockIdentityError::from(e)),
+        };
+        if !device.starts_with(root.join("dev")) {
+            return Err(BlockIdentityError::NotFound(dev.to_string()));
+        }
+        let mut tags: Vec<Option<String>> = vec![];
+        for &(_, dir) in TAGS {
+            tags.push(BlockIdentity::find_link(&disk_by.join(dir), &device)?);
+        }
+        let name = device
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let dm_name = if name.starts_with("dm-") {
+            let p = root.join(SYS_BLOCK.trim_start_matches('/'))
+                .join(&name)
+                .join("dm/name");
+            fs::read_to_string(p).ok().map(|n| n.trim().to_string())
+        } else {
+            None
+        };
+        let device = Path::new("/").join(device.strip_prefix(root).unwrap_or(&device));
+        let mut tags = tags.into_iter();
+        Ok(BlockIdentity {
+            device,
+            dm_name,
+            uuid: tags.next().and_then(|t| t),
+            label: tags.next().and_then(|t| t),
+            partuuid: tags.next().and_then(|t| t),
+            partlabel: tags.next().and_then(|t| t),
+        })
+    }
+
+    /// Name of the symlink in dir pointing at device, a missing directory just means udev
+    /// has nothing of that kind
+    fn find_link(dir: &Path, device: &Path) -> Result<Option<String>, io::Error> {
+        let entries = match fs::read_dir(dir) {
+            Ok(e) => e,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        for entry in entries {
+            let path = entry?.path();
+            if let Ok(target) = fs::canonicalize(&path) {
+                if target == device {
+                    let name = path.file_name().unwrap().to_string_lossy();
+                    return Ok(Some(unescape_tag(&name)));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// udev encodes characters such as space and slash in link names as \xNN
+fn unescape_tag(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 3 < bytes.len() && bytes[i + 1] == b'x' {
+            let hex = str::from_utf8(&bytes[i + 2..i + 4]).unwrap_or("");
+            if let Ok(v) = u8::from_str_radix(hex, 16) {
+                out.push(v);
+                i += 4;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn escape_tag(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        match c {
+            ' ' | '/' | '\\' | '"' | '\'' => out.push_str(&format!("\\x{:02x}", c as u32)),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[test]
+fn test_block_identity_resolve() {
+    use testtree::TestTree;
+
+    let tree = TestTree::new("blockid");
+    for dir in &["dev/mapper", "dev/disk/by-uuid", "dev/disk/by-label"] {
+        tree.mkdir(dir);
+    }
+    tree.write("dev/sda1", "");
+    tree.write("dev/dm-0", "");
+    tree.write("sys/block/dm-0/dm/name", "vg-root\n");
+    tree.symlink("../dm-0", "dev/mapper/vg-root");
+    tree.symlink("../../sda1", "dev/disk/by-uuid/4AE7-B622");
+    tree.symlink("../../dm-0", "dev/disk/by-uuid/46bfd5e8");
+    tree.symlink("../../dm-0", "dev/disk/by-label/my\\x20root");
+    let root = tree.path();
+
+    let sda = BlockIdentity::resolve_in(root, "/dev/sda1").unwrap();
+    assert_eq!(sda.get_uuid(), Some("4AE7-B622"));
+    assert_eq!(sda.get_label(), None);
+
+    let dm = BlockIdentity::resolve_in(root, "/dev/mapper/vg-root").unwrap();
+    let by_label = BlockIdentity::resolve_in(root, "LABEL=my root").unwrap();
+    assert_eq!(dm, by_label);
+    assert_eq!(dm.get_device(), Path::new("/dev/dm-0"));
+    assert_eq!(dm.get_dm_name(), Some("vg-root"));
+    assert_eq!(dm.get_uuid(), Some("46bfd5e8"));
+    assert_eq!(dm.get_label(), Some("my root"));
+
+    match BlockIdentity::resolve_in(root, "UUID=missing") {
+        Err(BlockIdentityError::NotFound(d)) => assert_eq!(d, "UUID=missing"),
+        r => panic!("expected NotFound, got {:?}", r),
+    }
+}
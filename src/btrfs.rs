@@ -0,0 +1,410 @@
+use std::ffi::CStr;
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::mem;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use libc;
+use mounts::Mount;
+use sysfs::{list_dir, read_attr, read_num};
+
+pub static SYS_BTRFS: &str = "/sys/fs/btrfs";
+
+/// _IOR(0x94, 31, struct btrfs_ioctl_fs_info_args)
+const BTRFS_IOC_FS_INFO: u32 = 0x8400_941f;
+/// _IOWR(0x94, 30, struct btrfs_ioctl_dev_info_args)
+const BTRFS_IOC_DEV_INFO: u32 = 0xd000_941e;
+
+/// struct btrfs_ioctl_fs_info_args, only the leading fields are needed
+#[repr(C)]
+struct FsInfoArgs {
+    max_id: u64,
+    num_devices: u64,
+    rest: [u8; 1008],
+}
+
+/// struct btrfs_ioctl_dev_info_args
+#[repr(C)]
+struct DevInfoArgs {
+    devid: u64,
+    uuid: [u8; 16],
+    bytes_used: u64,
+    total_bytes: u64,
+    fsid: [u8; 16],
+    unused: [u64; 377],
+    path: [u8; 1024],
+}
+
+/// Block group types under allocation/
+static SPACE_KINDS: &[&str] = &["data", "metadata", "system"];
+
+/// Space of one block group type. Bytes are logical, disk_* counts every copy the profile
+/// keeps, e.g. twice the logical size for raid1 or dup
+#[derive(Debug)]
+pub struct BtrfsSpace {
+    kind: String,
+    profile: String,
+    total_bytes: u64,
+    bytes_used: u64,
+    disk_total: u64,
+    disk_used: u64,
+}
+
+impl PartialEq for BtrfsSpace {
+    fn eq(&self, other: &BtrfsSpace) -> bool {
+        (self.kind == other.kind) & (self.profile == other.profile)
+            & (self.total_bytes == other.total_bytes) & (self.bytes_used == other.bytes_used)
+            & (self.disk_total == other.disk_total) & (self.disk_used == other.disk_used)
+    }
+}
+
+impl fmt::Display for BtrfsSpace {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "{}, {}: total={} used={}",
+            self.kind, self.profile, self.total_bytes, self.bytes_used
+        )
+    }
+}
+
+impl BtrfsSpace {
+    /// data, metadata or system
+    pub fn get_kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// single, dup, raid1 ... as shown by btrfs filesystem df
+    pub fn get_profile(&self) -> &str {
+        &self.profile
+    }
+
+    /// Logical bytes allocated to block groups of this type
+    pub fn get_total_bytes(&self) -> u64 {
+        self.total_bytes
+    }
+
+    pub fn get_bytes_used(&self) -> u64 {
+        self.bytes_used
+    }
+
+    /// Raw device bytes allocated, including every copy
+    pub fn get_disk_total(&self) -> u64 {
+        self.disk_total
+    }
+
+    pub fn get_disk_used(&self) -> u64 {
+        self.disk_used
+    }
+
+    pub fn used_percent(&self) -> f64 {
+        if self.total_bytes == 0 {
+            return 0.0;
+        }
+        self.bytes_used as f64 / self.total_bytes as f64 * 100.0
+    }
+
+    fn read(dir: &Path, kind: &str) -> BtrfsSpace {
+        let profile = list_dir(dir)
+            .into_iter()
+            .find(|n| dir.join(n).is_dir())
+            .unwrap_or_else(|| "single".to_string());
+        BtrfsSpace {
+            kind: kind.to_string(),
+            profile,
+            total_bytes: read_num(dir, "total_bytes"),
+            bytes_used: read_num(dir, "bytes_used"),
+            disk_total: read_num(dir, "disk_total"),
+            disk_used: read_num(dir, "disk_used"),
+        }
+    }
+}
+
+/// A device of the filesystem. sysfs only has its size, the id and the bytes allocated
+/// on it come from the BTRFS_IOC_DEV_INFO ioctl on the mounted filesystem
+#[derive(Debug)]
+pub struct BtrfsDevice {
+    name: String,
+    size: u64,
+    devid: Option<u64>,
+    allocated: Option<u64>,
+}
+
+impl PartialEq for BtrfsDevice {
+    fn eq(&self, other: &BtrfsDevice) -> bool {
+        (self.name == other.name) & (self.size == other.size) & (self.devid == other.devid)
+            & (self.allocated == other.allocated)
+    }
+}
+
+impl BtrfsDevice {
+    /// Kernel name, e.g. sda2 or dm-1
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Size in bytes
+    pub fn get_size(&self) -> u64 {
+        self.size
+    }
+
+    /// Device id within the filesystem, None when it wasn't read through a mount
+    pub fn get_devid(&self) -> Option<u64> {
+        self.devid
+    }
+
+    /// Bytes allocated to block groups on this device, None when it wasn't read through a
+    /// mount
+    pub fn get_allocated(&self) -> Option<u64> {
+        self.allocated
+    }
+
+    /// Bytes on this device not yet allocated to any block group. A multi device
+    /// filesystem can run out of space for raid1 chunks while one device still has room
+    pub fn unallocated(&self) -> Option<u64> {
+        self.allocated.map(|a| self.size.saturating_sub(a))
+    }
+}
+
+/// What BTRFS_IOC_DEV_INFO reports for one device
+struct DevInfo {
+    devid: u64,
+    path: PathBuf,
+    bytes_used: u64,
+}
+
+/// A mounted btrfs filesystem from /sys/fs/btrfs/<uuid>
+#[derive(Debug)]
+pub struct BtrfsFs {
+    uuid: String,
+    label: String,
+    devices: Vec<BtrfsDevice>,
+    spaces: Vec<BtrfsSpace>,
+    global_rsv_size: u64,
+}
+
+impl PartialEq for BtrfsFs {
+    fn eq(&self, other: &BtrfsFs) -> bool {
+        (self.uuid == other.uuid) & (self.label == other.label)
+            & (self.devices == other.devices) & (self.spaces == other.spaces)
+            & (self.global_rsv_size == other.global_rsv_size)
+    }
+}
+
+impl fmt::Display for BtrfsFs {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "Btrfs: {} Label: {} Devices: {} Unallocated: {}",
+            self.uuid,
+            self.label,
+            self.devices.len(),
+            self.unallocated()
+        )
+    }
+}
+
+impl BtrfsFs {
+    pub fn get_uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    pub fn get_label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn get_devices(&self) -> &Vec<BtrfsDevice> {
+        &self.devices
+    }
+
+    pub fn get_spaces(&self) -> &Vec<BtrfsSpace> {
+        &self.spaces
+    }
+
+    /// Metadata space kept back for operations that must not fail, e.g. deletes
+    pub fn get_global_rsv_size(&self) -> u64 {
+        self.global_rsv_size
+    }
+
+    /// data, metadata or system
+    pub fn space(&self, kind: &str) -> Option<&BtrfsSpace> {
+        self.spaces.iter().find(|s| s.kind == kind)
+    }
+
+    /// Raw device bytes not yet allocated to any block group. statvfs can show free space
+    /// while this is 0 and metadata is full, which is when btrfs reports ENOSPC
+    pub fn unallocated(&self) -> u64 {
+        let size: u64 = self.devices.iter().map(|d| d.size).sum();
+        let allocated: u64 = self.spaces.iter().map(|s| s.disk_total).sum();
+        size.saturating_sub(allocated)
+    }
+
+    /// The btrfs filesystem a mount belongs to, found through the mount's device. Per
+    /// device allocation is filled in when the ioctl on the mount point succeeds
+    pub fn for_mount(mount: &Mount) -> Option<BtrfsFs> {
+        if mount.get_fs_type() != "btrfs" {
+            return None;
+        }
+        let mut btrfs = BtrfsFs::find(Path::new(SYS_BTRFS), mount.get_dev())?;
+        if let Ok(infos) = dev_info(mount.get_mnt()) {
+            btrfs.apply_dev_info(&infos);
+        }
+        Some(btrfs)
+    }
+
+    /// Devices are matched on kernel name, the ioctl gives the path the device was
+    /// added with which may be a symlink such as /dev/mapper/vg-data
+    fn apply_dev_info(&mut self, infos: &[DevInfo]) {
+        for info in infos {
+            let path = fs::canonicalize(&info.path).unwrap_or_else(|_| info.path.clone());
+            let name = match path.file_name() {
+                Some(n) => n.to_string_lossy().into_owned(),
+                None => continue,
+            };
+            if let Some(dev) = self.devices.iter_mut().find(|d| d.name == name) {
+                dev.devid = Some(info.devid);
+                dev.allocated = Some(info.bytes_used);
+            }
+        }
+    }
+
+    /// Multi device filesystems list every member under devices/ so matching the mount
+    /// source against them works whichever member was mounted
+    fn find(sys_btrfs: &Path, dev: &Path) -> Option<BtrfsFs> {
+        let dev = fs::canonicalize(dev).unwrap_or_else(|_| dev.to_path_buf());
+        let name = dev.file_name()?.to_string_lossy().into_owned();
+        for uuid in list_dir(sys_btrfs) {
+            let dir = sys_btrfs.join(&uuid);
+            if list_dir(&dir.join("devices")).contains(&name) {
+                return BtrfsFs::read(&dir).ok();
+            }
+        }
+        None
+    }
+
+    fn read(dir: &Path) -> Result<BtrfsFs, io::Error> {
+        let uuid = dir.file_name().unwrap().to_string_lossy().into_owned();
+        let allocation = dir.join("allocation");
+        if !allocation.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No allocation info for {}", uuid),
+            ));
+        }
+        let devices = list_dir(&dir.join("devices"))
+            .into_iter()
+            .map(|name| BtrfsDevice {
+                size: read_num(&dir.join("devices").join(&name), "size") * 512,
+                name,
+                devid: None,
+                allocated: None,
+            })
+            .collect();
+        let spaces = SPACE_KINDS
+            .iter()
+            .filter(|k| allocation.join(k).is_dir())
+            .map(|k| BtrfsSpace::read(&allocation.join(k), k))
+            .collect();
+        Ok(BtrfsFs {
+            uuid,
+            label: read_attr(dir, "label").unwrap_or_default(),
+            devices,
+            spaces,
+            global_rsv_size: read_num(&allocation, "global_rsv_size"),
+        })
+    }
+}
+
+/// Every device of the filesystem mounted at mnt. Device ids can have gaps after a
+/// device is removed, the kernel returns ENODEV for those
+fn dev_info(mnt: &Path) -> Result<Vec<DevInfo>, io::Error> {
+    let file = File::open(mnt)?;
+    let fd = file.as_raw_fd();
+    let mut fs_info: FsInfoArgs = unsafe { mem::zeroed() };
+    if unsafe { libc::ioctl(fd, BTRFS_IOC_FS_INFO as _, &mut fs_info) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let mut infos = vec![];
+    for devid in 1..=fs_info.max_id {
+        let mut args: DevInfoArgs = unsafe { mem::zeroed() };
+        args.devid = devid;
+        if unsafe { libc::ioctl(fd, BTRFS_IOC_DEV_INFO as _, &mut args) } != 0 {
+            let e = io::Error::last_os_error();
+            if e.raw_os_error() == Some(libc::ENODEV) {
+                continue;
+            }
+            return Err(e);
+        }
+        let path = CStr::from_bytes_until_nul(&args.path)
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        infos.push(DevInfo {
+            devid: args.devid,
+            path: PathBuf::from(path),
+            bytes_used: args.bytes_used,
+        })
+    }
+    Ok(infos)
+}
+
+#[test]
+fn test_btrfs_read() {
+    use testtree::TestTree;
+
+    let tree = TestTree::new("btrfs");
+    let fs_dir = "46bfd5e8-5d2c-4c1d-9f4b-0c6c7f0e9a11";
+    let attrs: &[(&str, &str)] = &[
+        ("label", "pool\n"),
+        ("devices/sdb/size", "41943040"),
+        ("devices/sdc/size", "41943040"),
+        ("allocation/global_rsv_size", "16777216"),
+        ("allocation/data/total_bytes", "8589934592"),
+        ("allocation/data/bytes_used", "6442450944"),
+        ("allocation/data/disk_total", "17179869184"),
+        ("allocation/data/disk_used", "12884901888"),
+        ("allocation/data/raid1/total_bytes", "8589934592"),
+        ("allocation/metadata/total_bytes", "1073741824"),
+        ("allocation/metadata/bytes_used", "268435456"),
+        ("allocation/metadata/disk_total", "2147483648"),
+        ("allocation/metadata/disk_used", "536870912"),
+        ("allocation/metadata/raid1/total_bytes", "1073741824"),
+        ("allocation/system/total_bytes", "8388608"),
+        ("allocation/system/disk_total", "16777216"),
+        ("allocation/system/raid1/total_bytes", "8388608"),
+    ];
+    for &(file, value) in attrs {
+        tree.write(&format!("{}/{}", fs_dir, file), value);
+    }
+
+    let btrfs = BtrfsFs::find(tree.path(), Path::new("/dev/sdc")).unwrap();
+    assert!(BtrfsFs::find(tree.path(), Path::new("/dev/sdd")).is_none());
+    assert_eq!(btrfs.get_label(), "pool");
+    assert_eq!(btrfs.get_devices().len(), 2);
+    let data = btrfs.space("data").unwrap();
+    assert_eq!(data.get_profile(), "raid1");
+    assert_eq!(data.used_percent(), 75.0);
+    assert_eq!(btrfs.space("metadata").unwrap().get_disk_total(), 2147483648);
+    assert_eq!(btrfs.unallocated(), 42949672960 - 17179869184 - 2147483648 - 16777216);
+
+    assert_eq!(mem::size_of::<FsInfoArgs>(), 1024);
+    assert_eq!(mem::size_of::<DevInfoArgs>(), 4096);
+    let mut btrfs = btrfs;
+    assert_eq!(btrfs.get_devices()[0].get_allocated(), None);
+    btrfs.apply_dev_info(&[
+        DevInfo {
+            devid: 1,
+            path: PathBuf::from("/nonexistent/sdb"),
+            bytes_used: 10737418240,
+        },
+        DevInfo {
+            devid: 3,
+            path: PathBuf::from("/nonexistent/sdc"),
+            bytes_used: 9663676416,
+        },
+    ]);
+    let sdc = &btrfs.get_devices()[1];
+    assert_eq!(sdc.get_devid(), Some(3));
+    assert_eq!(sdc.unallocated(), Some(21474836480 - 9663676416));
+}
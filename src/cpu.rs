@@ -0,0 +1,536 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::path::Path;
+use std::io::Read;
+use sysfs::{list_dir, read_attr, read_num};
+
+pub static CPU_ONLINE: &str = "/sys/devices/system/cpu/online";
+pub static SYS_CPU: &str = "/sys/devices/system/cpu";
+pub static CPUINFO: &str = "/proc/cpuinfo";
+
+/// Number of CPUs the scheduler can currently run tasks on. Without sysfs the processors
+/// listed in /proc/cpuinfo are counted, which also only shows online CPUs
+pub fn online_cpus() -> usize {
+    match fs::read_to_string(CPU_ONLINE) {
+        Ok(data) => parse_cpu_list(&data).len(),
+        Err(_) => CpuInfo::all().len(),
+    }
+}
+
+/// Expand a kernel cpu list such as "0-3,8,10-11" into the CPU ids it contains
+pub fn parse_cpu_list(list: &str) -> Vec<usize> {
+    let mut cpus = vec![];
+    for range in list.trim().split(',') {
+        if range.is_empty() {
+            continue;
+        }
+        let bounds: Vec<&str> = range.split('-').collect();
+        let start = bounds[0].parse::<usize>().unwrap();
+        let end = match bounds.get(1) {
+            Some(e) => e.parse::<usize>().unwrap(),
+            None => start,
+        };
+        for cpu in start..end + 1 {
+            cpus.push(cpu)
+        }
+    }
+    cpus
+}
+
+/// One processor block of /proc/cpuinfo. The keys differ between architectures, the
+/// common ones have fields and the rest are kept by name
+#[derive(Debug)]
+pub struct CpuInfo {
+    processor: usize,
+    vendor_id: String,
+    model_name: String,
+    microcode: Option<String>,
+    mhz: Option<f64>,
+    cache_size: Option<u64>,
+    bogomips: Option<f64>,
+    flags: Vec<String>,
+    other: HashMap<String, String>,
+}
+
+impl PartialEq for CpuInfo {
+    fn eq(&self, other: &CpuInfo) -> bool {
+        (self.processor == other.processor) & (self.vendor_id == other.vendor_id)
+            & (self.model_name == other.model_name) & (self.microcode == other.microcode)
+            & (self.mhz == other.mhz) & (self.cache_size == other.cache_size)
+            & (self.bogomips == other.bogomips) & (self.flags == other.flags)
+            & (self.other == other.other)
+    }
+}
+
+impl fmt::Display for CpuInfo {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmtr, "CPU{}: {}", self.processor, self.model_name)?;
+        if let Some(mhz) = self.mhz {
+            write!(fmtr, " {:.0} MHz", mhz)?;
+        }
+        Ok(())
+    }
+}
+
+impl CpuInfo {
+    /// Every online CPU
+    pub fn all() -> Vec<CpuInfo> {
+        let mut data = String::new();
+        let mut f = File::open(Path::new(CPUINFO)).expect("Unable to open file");
+        f.read_to_string(&mut data).expect("Unable to read string");
+        CpuInfo::parse(&data)
+    }
+
+    pub fn get_processor(&self) -> usize {
+        self.processor
+    }
+
+    /// e.g. GenuineIntel, AuthenticAMD, empty on ARM
+    pub fn get_vendor_id(&self) -> &str {
+        &self.vendor_id
+    }
+
+    pub fn get_model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    pub fn get_microcode(&self) -> Option<&str> {
+        self.microcode.as_deref()
+    }
+
+    /// Frequency when the file was read, x86 only
+    pub fn get_mhz(&self) -> Option<f64> {
+        self.mhz
+    }
+
+    /// Cache size in kB, x86 only
+    pub fn get_cache_size(&self) -> Option<u64> {
+        self.cache_size
+    }
+
+    pub fn get_bogomips(&self) -> Option<f64> {
+        self.bogomips
+    }
+
+    /// CPU feature flags, "flags" on x86 and "Features" on ARM
+    pub fn get_flags(&self) -> &Vec<String> {
+        &self.flags
+    }
+
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+
+    /// Any key without a dedicated field, e.g. "cpu family" or "CPU implementer"
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.other.get(key).map(|v| v.as_str())
+    }
+
+    /// Blocks are separated by blank lines, ARM adds machine wide lines such as
+    /// "Hardware" at the end which aren't a processor and are skipped
+    fn parse(s: &str) -> Vec<CpuInfo> {
+        let mut cpus = vec![];
+        for block in s.split("\n\n") {
+            let mut values: HashMap<String, String> = HashMap::new();
+            for line in block.lines() {
+                if let Some(i) = line.find(':') {
+                    values.insert(line[..i].trim().to_string(), line[i + 1..].trim().to_string());
+                }
+            }
+            let processor = match values.remove("processor").and_then(|p| p.parse().ok()) {
+                Some(p) => p,
+                None => continue,
+            };
+            let mut take = |keys: &[&str]| {
+                keys.iter().filter_map(|k| values.remove(*k)).next()
+            };
+            let vendor_id = take(&["vendor_id"]).unwrap_or_default();
+            let model_name = take(&["model name"]).unwrap_or_default();
+            let microcode = take(&["microcode"]);
+            let mhz = take(&["cpu MHz"]).and_then(|m| m.parse().ok());
+            let cache_size = take(&["cache size"])
+                .and_then(|c| c.split_whitespace().next().and_then(|c| c.parse().ok()));
+            let bogomips = take(&["bogomips", "BogoMIPS"]).and_then(|b| b.parse().ok());
+            let flags = take(&["flags", "Features"])
+                .map(|f| f.split_whitespace().map(|f| f.to_string()).collect())
+                .unwrap_or_default();
+            cpus.push(CpuInfo {
+                processor,
+                vendor_id,
+                model_name,
+                microcode,
+                mhz,
+                cache_size,
+                bogomips,
+                flags,
+                other: values,
+            })
+        }
+        cpus
+    }
+}
+
+/// cpufreq policy of a CPU, frequencies in kHz. A frequency is None when its file is
+/// missing or unreadable, scaling_cur_freq is absent with some drivers and needs root with
+/// others
+#[derive(Debug)]
+pub struct CpuFreq {
+    current: Option<u64>,
+    min: Option<u64>,
+    max: Option<u64>,
+    governor: String,
+    driver: String,
+}
+
+impl PartialEq for CpuFreq {
+    fn eq(&self, other: &CpuFreq) -> bool {
+        (self.current == other.current) & (self.min == other.min) & (self.max == other.max)
+            & (self.governor == other.governor) & (self.driver == other.driver)
+    }
+}
+
+impl CpuFreq {
+    pub fn get_current(&self) -> Option<u64> {
+        self.current
+    }
+
+    /// Lowest frequency the governor may pick, scaling_min_freq
+    pub fn get_min(&self) -> Option<u64> {
+        self.min
+    }
+
+    pub fn get_max(&self) -> Option<u64> {
+        self.max
+    }
+
+    /// e.g. performance, powersave, schedutil
+    pub fn get_governor(&self) -> &str {
+        &self.governor
+    }
+
+    /// e.g. intel_pstate, acpi-cpufreq
+    pub fn get_driver(&self) -> &str {
+        &self.driver
+    }
+
+    fn read(dir: &Path) -> Option<CpuFreq> {
+        if !dir.is_dir() {
+            return None;
+        }
+        let freq = |attr: &str| read_attr(dir, attr).ok().and_then(|s| s.parse::<u64>().ok());
+        Some(CpuFreq {
+            current: freq("scaling_cur_freq"),
+            min: freq("scaling_min_freq"),
+            max: freq("scaling_max_freq"),
+            governor: read_attr(dir, "scaling_governor").unwrap_or_default(),
+            driver: read_attr(dir, "scaling_driver").unwrap_or_default(),
+        })
+    }
+}
+
+/// Where a logical CPU sits in the machine, from sysfs. Offline CPUs have no topology so
+/// their ids are 0 and sibling lists empty
+#[derive(Debug)]
+pub struct CpuTopology {
+    cpu: usize,
+    online: bool,
+    package_id: usize,
+    die_id: usize,
+    core_id: usize,
+    thread_siblings: Vec<usize>,
+    core_siblings: Vec<usize>,
+    node: Option<usize>,
+    freq: Option<CpuFreq>,
+}
+
+impl PartialEq for CpuTopology {
+    fn eq(&self, other: &CpuTopology) -> bool {
+        (self.cpu == other.cpu) & (self.online == other.online)
+            & (self.package_id == other.package_id) & (self.die_id == other.die_id)
+            & (self.core_id == other.core_id) & (self.thread_siblings == other.thread_siblings)
+            & (self.core_siblings == other.core_siblings) & (self.node == other.node)
+            & (self.freq == other.freq)
+    }
+}
+
+impl fmt::Display for CpuTopology {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "CPU{}: Package: {} Die: {} Core: {}",
+            self.cpu, self.package_id, self.die_id, self.core_id
+        )?;
+        if let Some(node) = self.node {
+            write!(fmtr, " Node: {}", node)?;
+        }
+        if !self.online {
+            write!(fmtr, " offline")?;
+        }
+        Ok(())
+    }
+}
+
+impl CpuTopology {
+    pub fn get_cpu(&self) -> usize {
+        self.cpu
+    }
+
+    pub fn is_online(&self) -> bool {
+        self.online
+    }
+
+    /// Physical socket
+    pub fn get_package_id(&self) -> usize {
+        self.package_id
+    }
+
+    /// Die within the package, 0 before Linux 5.2 or on single die parts
+    pub fn get_die_id(&self) -> usize {
+        self.die_id
+    }
+
+    /// Core within the package, not unique across packages
+    pub fn get_core_id(&self) -> usize {
+        self.core_id
+    }
+
+    /// Hyperthreads sharing this CPU's core, including itself
+    pub fn get_thread_siblings(&self) -> &Vec<usize> {
+        &self.thread_siblings
+    }
+
+    /// CPUs in the same package, including itself
+    pub fn get_core_siblings(&self) -> &Vec<usize> {
+        &self.core_siblings
+    }
+
+    /// NUMA node, None on kernels built without NUMA
+    pub fn get_node(&self) -> Option<usize> {
+        self.node
+    }
+
+    /// None when no cpufreq driver is loaded, as is common in VMs
+    pub fn get_freq(&self) -> Option<&CpuFreq> {
+        self.freq.as_ref()
+    }
+
+    fn read(dir: &Path, cpu: usize, online: bool) -> CpuTopology {
+        let topology = dir.join("topology");
+        let list = |attr: &str| {
+            read_attr(&topology, attr)
+                .map(|l| parse_cpu_list(&l))
+                .unwrap_or_default()
+        };
+        let node = list_dir(dir)
+            .iter()
+            .filter_map(|n| n.strip_prefix("node").and_then(|n| n.parse::<usize>().ok()))
+            .next();
+        CpuTopology {
+            cpu,
+            online,
+            package_id: read_num(&topology, "physical_package_id") as usize,
+            die_id: read_num(&topology, "die_id") as usize,
+            core_id: read_num(&topology, "core_id") as usize,
+            thread_siblings: list("thread_siblings_list"),
+            core_siblings: list("core_siblings_list"),
+            node,
+            freq: CpuFreq::read(&dir.join("cpufreq")),
+        }
+    }
+}
+
+/// CPU inventory from /sys/devices/system/cpu
+#[derive(Debug)]
+pub struct Cpus {
+    cpus: Vec<CpuTopology>,
+    online: Vec<usize>,
+    offline: Vec<usize>,
+    present: Vec<usize>,
+    possible: Vec<usize>,
+    isolated: Vec<usize>,
+    nohz_full: Vec<usize>,
+}
+
+impl Cpus {
+    pub fn get_cpus(self) -> Vec<CpuTopology> {
+        self.cpus
+    }
+
+    pub fn new() -> Cpus {
+        Cpus::read(Path::new(SYS_CPU))
+    }
+
+    /// Topology of a CPU, e.g. for Process::get_processor
+    pub fn get(&self, cpu: usize) -> Option<&CpuTopology> {
+        self.cpus.iter().find(|c| c.cpu == cpu)
+    }
+
+    pub fn get_online(&self) -> &Vec<usize> {
+        &self.online
+    }
+
+    pub fn get_offline(&self) -> &Vec<usize> {
+        &self.offline
+    }
+
+    /// CPUs physically present, online or not
+    pub fn get_present(&self) -> &Vec<usize> {
+        &self.present
+    }
+
+    /// CPUs that could ever be brought online, including hotplug slots
+    pub fn get_possible(&self) -> &Vec<usize> {
+        &self.possible
+    }
+
+    /// CPUs removed from scheduler load balancing with isolcpus=
+    pub fn get_isolated(&self) -> &Vec<usize> {
+        &self.isolated
+    }
+
+    /// CPUs running without the periodic tick when they have a single task, nohz_full=
+    pub fn get_nohz_full(&self) -> &Vec<usize> {
+        &self.nohz_full
+    }
+
+    pub fn online_count(&self) -> usize {
+        self.online.len()
+    }
+
+    /// Online CPUs the scheduler balances normal tasks across
+    pub fn housekeeping(&self) -> Vec<usize> {
+        self.online
+            .iter()
+            .filter(|c| !self.isolated.contains(c))
+            .cloned()
+            .collect()
+    }
+
+    /// Number of sockets with an online CPU
+    pub fn packages(&self) -> usize {
+        let mut packages: Vec<usize> = self.cpus
+            .iter()
+            .filter(|c| c.online)
+            .map(|c| c.package_id)
+            .collect();
+        packages.sort();
+        packages.dedup();
+        packages.len()
+    }
+
+    /// Number of physical cores with an online CPU
+    pub fn cores(&self) -> usize {
+        let mut cores: Vec<(usize, usize, usize)> = self.cpus
+            .iter()
+            .filter(|c| c.online)
+            .map(|c| (c.package_id, c.die_id, c.core_id))
+            .collect();
+        cores.sort();
+        cores.dedup();
+        cores.len()
+    }
+
+    fn read(root: &Path) -> Cpus {
+        let list = |attr: &str| {
+            read_attr(root, attr)
+                .map(|l| parse_cpu_list(&l))
+                .unwrap_or_default()
+        };
+        let online = list("online");
+        let mut cpus: Vec<CpuTopology> = list_dir(root)
+            .iter()
+            .filter_map(|n| n.strip_prefix("cpu").and_then(|n| n.parse::<usize>().ok()))
+            .map(|cpu| {
+                let dir = root.join(format!("cpu{}", cpu));
+                CpuTopology::read(&dir, cpu, online.contains(&cpu))
+            })
+            .collect();
+        cpus.sort_by_key(|c| c.cpu);
+        Cpus {
+            cpus,
+            offline: list("offline"),
+            present: list("present"),
+            possible: list("possible"),
+            isolated: list("isolated"),
+            nohz_full: list("nohz_full"),
+            online,
+        }
+    }
+}
+
+#[test]
+fn test_parse_cpu_list() {
+    assert_eq!(parse_cpu_list("0-3,8,10-11\n"), vec![0, 1, 2, 3, 8, 10, 11]);
+    assert_eq!(parse_cpu_list("0\n"), vec![0]);
+    assert_eq!(parse_cpu_list("\n"), Vec::<usize>::new())
+}
+
+#[test]
+fn test_cpu_inventory() {
+    use testtree::TestTree;
+
+    let cpuinfo = "processor\t: 0
+vendor_id\t: GenuineIntel
+cpu family\t: 6
+model name\t: Intel(R) Xeon(R) Processor
+microcode\t: 0x1
+cpu MHz\t\t: 2100.000
+cache size\t: 307200 KB
+flags\t\t: fpu vme avx2
+bogomips\t: 4200.00
+
+processor\t: 1
+BogoMIPS\t: 50.00
+Features\t: fp asimd evtstrm
+CPU implementer\t: 0x41
+
+Hardware\t: BCM2835
+";
+    let infos = CpuInfo::parse(cpuinfo);
+    assert_eq!(infos.len(), 2);
+    assert_eq!(infos[0].get_cache_size(), Some(307200));
+    assert_eq!(infos[0].get_mhz(), Some(2100.0));
+    assert!(infos[0].has_flag("avx2"));
+    assert_eq!(infos[0].get("cpu family"), Some("6"));
+    assert_eq!(infos[1].get_bogomips(), Some(50.0));
+    assert!(infos[1].has_flag("asimd"));
+
+    let tree = TestTree::new("cpu");
+    tree.write_all(&[
+        ("online", "0-2\n"),
+        ("offline", "3\n"),
+        ("possible", "0-3\n"),
+        ("present", "0-3\n"),
+        ("isolated", "2\n"),
+        ("cpu0/topology/physical_package_id", "0"),
+        ("cpu0/topology/core_id", "0"),
+        ("cpu0/topology/thread_siblings_list", "0-1"),
+        ("cpu0/topology/core_siblings_list", "0-2"),
+        ("cpu0/cpufreq/scaling_cur_freq", "1800000"),
+        ("cpu0/cpufreq/scaling_governor", "schedutil\n"),
+        ("cpu1/topology/physical_package_id", "0"),
+        ("cpu1/topology/core_id", "0"),
+        ("cpu2/topology/physical_package_id", "0"),
+        ("cpu2/topology/core_id", "1"),
+        ("cpu3/online", "0"),
+    ]);
+    tree.mkdir("cpu0/node0");
+
+    let cpus = Cpus::read(tree.path());
+    assert_eq!(cpus.online_count(), 3);
+    assert_eq!(cpus.get_offline(), &vec![3]);
+    assert_eq!(cpus.housekeeping(), vec![0, 1]);
+    assert_eq!(cpus.cores(), 2);
+    assert_eq!(cpus.packages(), 1);
+    let cpu0 = cpus.get(0).unwrap();
+    assert_eq!(cpu0.get_node(), Some(0));
+    assert_eq!(cpu0.get_thread_siblings(), &vec![0, 1]);
+    let freq = cpu0.get_freq().unwrap();
+    assert_eq!(freq.get_governor(), "schedutil");
+    assert_eq!(freq.get_current(), Some(1800000));
+    assert_eq!(freq.get_max(), None);
+    assert!(cpus.get(1).unwrap().get_freq().is_none());
+    assert!(!cpus.get(3).unwrap().is_online());
+}
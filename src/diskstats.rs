@@ -0,0 +1,399 @@
+use std::fs::File;
+use std::path::Path;
+use std::io::Read;
+use std::fmt;
+use mountinfo::MountInfo;
+use mounts::Mount;
+
+pub static DISKSTATS: &str = "/proc/diskstats";
+
+/// Sectors in diskstats are always 512 bytes regardless of the device sector size
+pub static SECTOR_SIZE: u64 = 512;
+
+/// I/O counters for one block device, times are in milliseconds
+#[derive(Debug)]
+pub struct DiskStat {
+    major: u32,
+    minor: u32,
+    name: String,
+    reads: u64,
+    reads_merged: u64,
+    sectors_read: u64,
+    read_ms: u64,
+    writes: u64,
+    writes_merged: u64,
+    sectors_written: u64,
+    write_ms: u64,
+    in_flight: u64,
+    io_ms: u64,
+    weighted_io_ms: u64,
+    discards: Option<u64>,
+    discards_merged: Option<u64>,
+    sectors_discarded: Option<u64>,
+    discard_ms: Option<u64>,
+    flushes: Option<u64>,
+    flush_ms: Option<u64>,
+}
+
+impl PartialEq for DiskStat {
+    fn eq(&self, other: &DiskStat) -> bool {
+        (self.major == other.major) & (self.minor == other.minor) & (self.name == other.name)
+            & (self.reads == other.reads) & (self.reads_merged == other.reads_merged)
+            & (self.sectors_read == other.sectors_read) & (self.read_ms == other.read_ms)
+            & (self.writes == other.writes) & (self.writes_merged == other.writes_merged)
+            & (self.sectors_written == other.sectors_written)
+            & (self.write_ms == other.write_ms) & (self.in_flight == other.in_flight)
+            & (self.io_ms == other.io_ms) & (self.weighted_io_ms == other.weighted_io_ms)
+            & (self.discards == other.discards)
+            & (self.discards_merged == other.discards_merged)
+            & (self.sectors_discarded == other.sectors_discarded)
+            & (self.discard_ms == other.discard_ms) & (self.flushes == other.flushes)
+            & (self.flush_ms == other.flush_ms)
+    }
+}
+
+impl fmt::Display for DiskStat {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "Device: {} ({}:{}) Reads: {} Writes: {} In flight: {}",
+            self.name, self.major, self.minor, self.reads, self.writes, self.in_flight
+        )
+    }
+}
+
+impl DiskStat {
+    pub fn get_major(&self) -> u32 {
+        self.major
+    }
+
+    pub fn get_minor(&self) -> u32 {
+        self.minor
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_reads(&self) -> u64 {
+        self.reads
+    }
+
+    pub fn get_reads_merged(&self) -> u64 {
+        self.reads_merged
+    }
+
+    pub fn get_sectors_read(&self) -> u64 {
+        self.sectors_read
+    }
+
+    pub fn get_read_ms(&self) -> u64 {
+        self.read_ms
+    }
+
+    pub fn get_writes(&self) -> u64 {
+        self.writes
+    }
+
+    pub fn get_writes_merged(&self) -> u64 {
+        self.writes_merged
+    }
+
+    pub fn get_sectors_written(&self) -> u64 {
+        self.sectors_written
+    }
+
+    pub fn get_write_ms(&self) -> u64 {
+        self.write_ms
+    }
+
+    /// I/Os currently in progress, the only field that isn't a counter
+    pub fn get_in_flight(&self) -> u64 {
+        self.in_flight
+    }
+
+    /// Time the device had I/O in progress (io_ticks)
+    pub fn get_io_ms(&self) -> u64 {
+        self.io_ms
+    }
+
+    /// Time spent on I/O multiplied by the number of I/Os in flight
+    pub fn get_weighted_io_ms(&self) -> u64 {
+        self.weighted_io_ms
+    }
+
+    /// Discard counters, None before Linux 4.18
+    pub fn get_discards(&self) -> Option<u64> {
+        self.discards
+    }
+
+    pub fn get_discards_merged(&self) -> Option<u64> {
+        self.discards_merged
+    }
+
+    pub fn get_sectors_discarded(&self) -> Option<u64> {
+        self.sectors_discarded
+    }
+
+    pub fn get_discard_ms(&self) -> Option<u64> {
+        self.discard_ms
+    }
+
+    /// Flush counters, None before Linux 5.5
+    pub fn get_flushes(&self) -> Option<u64> {
+        self.flushes
+    }
+
+    pub fn get_flush_ms(&self) -> Option<u64> {
+        self.flush_ms
+    }
+
+    /// iostat -x style rates between an earlier sample and this one taken `secs` apart
+    pub fn rate(&self, prev: &DiskStat, secs: f64) -> DiskRate {
+        let reads = self.reads.saturating_sub(prev.reads);
+        let writes = self.writes.saturating_sub(prev.writes);
+        let read_ms = self.read_ms.saturating_sub(prev.read_ms);
+        let write_ms = self.write_ms.saturating_sub(prev.write_ms);
+        let per_sec = |v: u64| if secs > 0.0 { v as f64 / secs } else { 0.0 };
+        let per_io = |ms: u64, ios: u64| if ios > 0 { ms as f64 / ios as f64 } else { 0.0 };
+        let interval_ms = secs * 1000.0;
+        DiskRate {
+            name: self.name.clone(),
+            reads_per_sec: per_sec(reads),
+            writes_per_sec: per_sec(writes),
+            read_bytes_per_sec: per_sec(
+                self.sectors_read.saturating_sub(prev.sectors_read) * SECTOR_SIZE,
+            ),
+            write_bytes_per_sec: per_sec(
+                self.sectors_written.saturating_sub(prev.sectors_written) * SECTOR_SIZE,
+            ),
+            r_await: per_io(read_ms, reads),
+            w_await: per_io(write_ms, writes),
+            await_ms: per_io(read_ms + write_ms, reads + writes),
+            util: if interval_ms > 0.0 {
+                (self.io_ms.saturating_sub(prev.io_ms) as f64 / interval_ms * 100.0).min(100.0)
+            } else {
+                0.0
+            },
+            queue_size: if interval_ms > 0.0 {
+                self.weighted_io_ms.saturating_sub(prev.weighted_io_ms) as f64 / interval_ms
+            } else {
+                0.0
+            },
+        }
+    }
+
+    fn parse(line: &str) -> Option<DiskStat> {
+        let split: Vec<&str> = line.split_whitespace().collect();
+        if split.len() < 14 {
+            return None;
+        }
+        let num = |i: usize| split[i].parse::<u64>().unwrap();
+        let opt = |i: usize| split.get(i).map(|v| v.parse::<u64>().unwrap());
+        Some(DiskStat {
+            major: split[0].parse::<u32>().unwrap(),
+            minor: split[1].parse::<u32>().unwrap(),
+            name: split[2].to_string(),
+            reads: num(3),
+            reads_merged: num(4),
+            sectors_read: num(5),
+            read_ms: num(6),
+            writes: num(7),
+            writes_merged: num(8),
+            sectors_written: num(9),
+            write_ms: num(10),
+            in_flight: num(11),
+            io_ms: num(12),
+            weighted_io_ms: num(13),
+            discards: opt(14),
+            discards_merged: opt(15),
+            sectors_discarded: opt(16),
+            discard_ms: opt(17),
+            flushes: opt(18),
+            flush_ms: opt(19),
+        })
+    }
+}
+
+/// Per second rates for a device, matching the columns of iostat -x
+#[derive(Debug)]
+pub struct DiskRate {
+    name: String,
+    reads_per_sec: f64,
+    writes_per_sec: f64,
+    read_bytes_per_sec: f64,
+    write_bytes_per_sec: f64,
+    r_await: f64,
+    w_await: f64,
+    await_ms: f64,
+    util: f64,
+    queue_size: f64,
+}
+
+impl fmt::Display for DiskRate {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "{}: r/s {:.2} w/s {:.2} rB/s {:.0} wB/s {:.0} await {:.2} aqu-sz {:.2} %util {:.1}",
+            self.name,
+            self.reads_per_sec,
+            self.writes_per_sec,
+            self.read_bytes_per_sec,
+            self.write_bytes_per_sec,
+            self.await_ms,
+            self.queue_size,
+            self.util
+        )
+    }
+}
+
+impl DiskRate {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_reads_per_sec(&self) -> f64 {
+        self.reads_per_sec
+    }
+
+    pub fn get_writes_per_sec(&self) -> f64 {
+        self.writes_per_sec
+    }
+
+    /// Reads plus writes per second
+    pub fn iops(&self) -> f64 {
+        self.reads_per_sec + self.writes_per_sec
+    }
+
+    pub fn get_read_bytes_per_sec(&self) -> f64 {
+        self.read_bytes_per_sec
+    }
+
+    pub fn get_write_bytes_per_sec(&self) -> f64 {
+        self.write_bytes_per_sec
+    }
+
+    /// Average time in ms for a read to be served, including time queued
+    pub fn get_r_await(&self) -> f64 {
+        self.r_await
+    }
+
+    /// Average time in ms for a write to be served, including time queued
+    pub fn get_w_await(&self) -> f64 {
+        self.w_await
+    }
+
+    /// Average time in ms for any I/O to be served
+    pub fn get_await(&self) -> f64 {
+        self.await_ms
+    }
+
+    /// Percentage of time the device was busy, devices serving requests in parallel
+    /// such as SSDs and RAID can be far from saturated at 100%
+    pub fn get_util(&self) -> f64 {
+        self.util
+    }
+
+    /// Average number of requests queued or in flight
+    pub fn get_queue_size(&self) -> f64 {
+        self.queue_size
+    }
+}
+
+#[derive(Debug)]
+pub struct DiskStats {
+    disks: Vec<DiskStat>,
+}
+
+impl DiskStats {
+    pub fn get_disks(self) -> Vec<DiskStat> {
+        self.disks
+    }
+
+    pub fn new() -> DiskStats {
+        let text = DiskStats::fetch();
+        DiskStats::parse(text)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&DiskStat> {
+        self.disks.iter().find(|d| d.name == name)
+    }
+
+    pub fn get_by_number(&self, major: u32, minor: u32) -> Option<&DiskStat> {
+        self.disks
+            .iter()
+            .find(|d| d.major == major && d.minor == minor)
+    }
+
+    /// Stats for the device behind a mount, None for filesystems without a block device
+    pub fn for_mount(&self, mount: &Mount) -> Option<&DiskStat> {
+        match mount.device_number() {
+            Ok((major, minor)) => self.get_by_number(major, minor),
+            Err(_) => None,
+        }
+    }
+
+    /// for_mount with mountinfo that was already read, for looking up many mounts
+    pub fn for_mount_in(&self, mount: &Mount, info: &MountInfo) -> Option<&DiskStat> {
+        match mount.device_number_in(info) {
+            Ok((major, minor)) => self.get_by_number(major, minor),
+            Err(_) => None,
+        }
+    }
+
+    /// Rates for every device present in both samples
+    pub fn rates(&self, prev: &DiskStats, secs: f64) -> Vec<DiskRate> {
+        let mut rates = vec![];
+        for disk in &self.disks {
+            if let Some(p) = prev.get_by_number(disk.major, disk.minor) {
+                rates.push(disk.rate(p, secs))
+            }
+        }
+        rates
+    }
+
+    fn fetch() -> String {
+        let path = Path::new(DISKSTATS);
+        let mut data = String::new();
+        let mut f = File::open(path).expect("Unable to open file");
+        f.read_to_string(&mut data).expect("Unable to read string");
+        data
+    }
+
+    fn parse(ds: String) -> DiskStats {
+        DiskStats {
+            disks: ds.lines().filter_map(DiskStat::parse).collect(),
+        }
+    }
+}
+
+#[test]
+fn test_diskstats_parse() {
+    let diskstats = " 259       0 nvme0n1 1114283 63513 84516526 361632 2453741 1282338 151009314 3284224 0 1070268 3710184 0 0 0 0 87562 64326
+ 259       2 nvme0n1p2 3210 11 424710 1306 2217 1208 174576 10151 0 8632 11458 0 0 0 0 0 0
+   8       0 sda 201 0 9768 51 0 0 0 0 0 72 51
+";
+    let ds = DiskStats::parse(diskstats.to_string());
+    let nvme = ds.get("nvme0n1").unwrap();
+    assert_eq!(nvme.get_sectors_written(), 151009314);
+    assert_eq!(nvme.get_io_ms(), 1070268);
+    assert_eq!(nvme.get_discards(), Some(0));
+    assert_eq!(nvme.get_flushes(), Some(87562));
+    let sda = ds.get_by_number(8, 0).unwrap();
+    assert_eq!(sda.get_reads(), 201);
+    assert_eq!(sda.get_discards(), None)
+}
+
+#[test]
+fn test_diskstats_rate() {
+    let before = DiskStat::parse("8 0 sda 1000 0 8000 500 2000 0 16000 3000 0 10000 20000").unwrap();
+    let after = DiskStat::parse("8 0 sda 1100 0 8800 600 2300 0 18400 3600 2 10500 21400").unwrap();
+    let r = after.rate(&before, 1.0);
+    assert_eq!(r.iops(), 400.0);
+    assert_eq!(r.get_read_bytes_per_sec(), 409600.0);
+    assert_eq!(r.get_r_await(), 1.0);
+    assert_eq!(r.get_w_await(), 2.0);
+    assert_eq!(r.get_await(), 1.75);
+    assert_eq!(r.get_util(), 50.0);
+    assert_eq!(r.get_queue_size(), 1.4)
+}
@@ -0,0 +1,370 @@
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use blockid::BlockIdentity;
+use mounts::{Atime, Mount, MountOptions, Mounts};
+
+pub static FSTAB: &str = "/etc/fstab";
+
+/// Options which come in a negative and positive form, the negative form is what
+/// /proc/mounts lists when it is in effect
+static OPTION_PAIRS: &[(&str, &str)] = &[
+    ("ro", "rw"),
+    ("noexec", "exec"),
+    ("nosuid", "suid"),
+    ("nodev", "dev"),
+    ("sync", "async"),
+];
+
+/// Filesystems mounted at runtime by snapd, container runtimes and FUSE tools rather than
+/// from fstab, fuse.* subtypes are matched too
+static RUNTIME_FS: &[&str] = &["squashfs", "overlay", "fuse"];
+
+/// fstab has the same six columns as /proc/mounts so entries are read as Mounts, the
+/// device is the spec as written and may be a tag such as UUID=... which
+/// Mount::identity resolves
+#[derive(Debug)]
+pub struct Fstab {
+    entries: Vec<Mount>,
+}
+
+impl Fstab {
+    pub fn get_entries(self) -> Vec<Mount> {
+        self.entries
+    }
+
+    pub fn new() -> Fstab {
+        let data = Fstab::fetch(Path::new(FSTAB));
+        Fstab::parse(&data)
+    }
+
+    /// Read an fstab from another path, e.g. in a chroot
+    pub fn from_path(path: &Path) -> Fstab {
+        let data = Fstab::fetch(path);
+        Fstab::parse(&data)
+    }
+
+    /// Compare with the live mount table
+    pub fn reconcile(&self) -> FstabReport {
+        FstabReport::compare(&self.entries, &Mounts::new().get_mounts(), Path::new("/"))
+    }
+
+    fn fetch(path: &Path) -> Vec<u8> {
+        let mut data = vec![];
+        let mut f = File::open(path).expect("Unable to open file");
+        f.read_to_end(&mut data).expect("Unable to read string");
+        data
+    }
+
+    /// Comments take up a whole line, a # inside a field is part of the field
+    fn parse(data: &[u8]) -> Fstab {
+        let mut table = vec![];
+        for line in data.split(|b| *b == b'\n') {
+            let start = line.iter().position(|b| *b != b' ' && *b != b'\t');
+            match start {
+                Some(i) if line[i] != b'#' => {
+                    table.extend_from_slice(line);
+                    table.push(b'\n');
+                }
+                _ => (),
+            }
+        }
+        Fstab {
+            entries: Mounts::parse(&table).get_mounts(),
+        }
+    }
+}
+
+/// An option fstab asks for which the live mount doesn't have
+#[derive(Debug)]
+pub struct OptionDrift {
+    mnt: PathBuf,
+    expected: String,
+    live: String,
+}
+
+impl PartialEq for OptionDrift {
+    fn eq(&self, other: &OptionDrift) -> bool {
+        (self.mnt == other.mnt) & (self.expected == other.expected) & (self.live == other.live)
+    }
+}
+
+impl fmt::Display for OptionDrift {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "Mount: {} fstab: {} live: {}",
+            self.mnt.display(),
+            self.expected,
+            self.live
+        )
+    }
+}
+
+impl OptionDrift {
+    pub fn get_mnt(&self) -> &Path {
+        &self.mnt
+    }
+
+    /// The option as written in fstab
+    pub fn get_expected(&self) -> &str {
+        &self.expected
+    }
+
+    /// The option actually in effect
+    pub fn get_live(&self) -> &str {
+        &self.live
+    }
+}
+
+/// A mount point with a different device mounted on it than the one fstab declares
+#[derive(Debug)]
+pub struct SourceMismatch {
+    mnt: PathBuf,
+    expected: PathBuf,
+    live: PathBuf,
+}
+
+impl PartialEq for SourceMismatch {
+    fn eq(&self, other: &SourceMismatch) -> bool {
+        (self.mnt == other.mnt) & (self.expected == other.expected) & (self.live == other.live)
+    }
+}
+
+impl fmt::Display for SourceMismatch {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "Mount: {} fstab: {} live: {}",
+            self.mnt.display(),
+            self.expected.display(),
+            self.live.display()
+        )
+    }
+}
+
+impl SourceMismatch {
+    pub fn get_mnt(&self) -> &Path {
+        &self.mnt
+    }
+
+    /// The source as written in fstab, e.g. UUID=...
+    pub fn get_expected(&self) -> &Path {
+        &self.expected
+    }
+
+    /// The source actually mounted
+    pub fn get_live(&self) -> &Path {
+        &self.live
+    }
+}
+
+/// Differences between fstab and the live mount table
+#[derive(Debug)]
+pub struct FstabReport {
+    missing: Vec<Mount>,
+    undeclared: Vec<Mount>,
+    mismatched: Vec<SourceMismatch>,
+    drift: Vec<OptionDrift>,
+}
+
+impl FstabReport {
+    /// fstab entries which should be mounted at boot but aren't, noauto and swap entries
+    /// are left out
+    pub fn get_missing(&self) -> &Vec<Mount> {
+        &self.missing
+    }
+
+    /// Live mounts of real filesystems that fstab doesn't mention. Pseudo and memory
+    /// filesystems are left out, as are squashfs, overlay and FUSE mounts which are made at
+    /// runtime. Network filesystems are included, so NFS mounted by autofs shows up here
+    pub fn get_undeclared(&self) -> &Vec<Mount> {
+        &self.undeclared
+    }
+
+    /// Mount points where the device mounted isn't the one fstab declares
+    pub fn get_mismatched(&self) -> &Vec<SourceMismatch> {
+        &self.mismatched
+    }
+
+    pub fn get_drift(&self) -> &Vec<OptionDrift> {
+        &self.drift
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.undeclared.is_empty() && self.mismatched.is_empty()
+            && self.drift.is_empty()
+    }
+
+    /// Entries are matched on mount point, when mounts are stacked the last one is the
+    /// one that is visible. Device sources are resolved under root
+    fn compare(fstab: &[Mount], live: &[Mount], root: &Path) -> FstabReport {
+        let mut report = FstabReport {
+            missing: vec![],
+            undeclared: vec![],
+            mismatched: vec![],
+            drift: vec![],
+        };
+        for entry in fstab {
+            if entry.get_fs_type() == "swap" || entry.get_options().has("noauto") {
+                continue;
+            }
+            match live.iter().rev().find(|m| m.get_mnt() == entry.get_mnt()) {
+                Some(m) => {
+                    if !FstabReport::same_source(entry, m, root) {
+                        report.mismatched.push(SourceMismatch {
+                            mnt: entry.get_mnt().to_path_buf(),
+                            expected: entry.get_dev().to_path_buf(),
+                            live: m.get_dev().to_path_buf(),
+                        })
+                    }
+                    report.drift.extend(FstabReport::drift(entry, m))
+                }
+                None => report.missing.push(entry.clone()),
+            }
+        }
+        for m in live {
+            let fs_type = m.get_fs_type();
+            if m.is_pseudo() || m.is_tmpfs() || RUNTIME_FS.contains(&fs_type)
+                || fs_type.starts_with("fuse.")
+            {
+                continue;
+            }
+            if !fstab.iter().any(|e| e.get_mnt() == m.get_mnt()) {
+                report.undeclared.push(m.clone())
+            }
+        }
+        report
+    }
+
+    /// Block devices are compared after resolving tags and symlinks, so UUID=... matches
+    /// /dev/sda1 and /dev/mapper/vg-root matches /dev/dm-0. Sources which aren't devices,
+    /// e.g. "server:/export", are compared as written. When only one side resolves, as with
+    /// /dev/root, there is nothing to go on and it is taken as a match. The source of a
+    /// bind mount shows up as the underlying device so those aren't checked
+    fn same_source(entry: &Mount, live: &Mount, root: &Path) -> bool {
+        if entry.get_options().has("bind") {
+            return true;
+        }
+        let resolve = |dev: &Path| {
+            dev.to_str()
+                .and_then(|d| BlockIdentity::resolve_in(root, d).ok())
+                .map(|i| i.get_device().to_path_buf())
+        };
+        match (resolve(entry.get_dev()), resolve(live.get_dev())) {
+            (Some(expected), Some(actual)) => expected == actual,
+            (None, None) => entry.get_dev() == live.get_dev(),
+            _ => true,
+        }
+    }
+
+    /// Only options fstab states explicitly are checked, "defaults" is not expanded
+    fn drift(entry: &Mount, live: &Mount) -> Vec<OptionDrift> {
+        let wanted = entry.get_options();
+        let actual = live.get_options();
+        let mut drift = vec![];
+        for &(neg, pos) in OPTION_PAIRS {
+            let expected = match wanted.get_flags().iter().rev().find(|f| *f == neg || *f == pos) {
+                Some(f) => f,
+                None => continue,
+            };
+            let live_flag = if actual.has(neg) { neg } else { pos };
+            if expected != live_flag {
+                drift.push(OptionDrift {
+                    mnt: entry.get_mnt().to_path_buf(),
+                    expected: expected.clone(),
+                    live: live_flag.to_string(),
+                })
+            }
+        }
+        if let Some(expected) = FstabReport::atime(wanted) {
+            if expected != actual.atime() {
+                drift.push(OptionDrift {
+                    mnt: entry.get_mnt().to_path_buf(),
+                    expected: atime_flag(expected).to_string(),
+                    live: atime_flag(actual.atime()).to_string(),
+                })
+            }
+        }
+        drift
+    }
+
+    /// MountOptions::atime assumes strictatime when nothing is listed, in fstab that just
+    /// means the kernel default
+    fn atime(opts: &MountOptions) -> Option<Atime> {
+        if opts.has("noatime") || opts.has("relatime") || opts.has("strictatime") {
+            Some(opts.atime())
+        } else {
+            None
+        }
+    }
+}
+
+fn atime_flag(atime: Atime) -> &'static str {
+    match atime {
+        Atime::Strictatime => "strictatime",
+        Atime::Relatime => "relatime",
+        Atime::Noatime => "noatime",
+    }
+}
+
+#[test]
+fn test_fstab_reconcile() {
+    use testtree::TestTree;
+
+    let fstab = Fstab::parse(
+        b"# /etc/fstab: static file system information.
+UUID=46bfd5e8-5d2c-4c1d-9f4b-0c6c7f0e9a11 /               ext4    errors=remount-ro 0       1
+  # indented comment
+LABEL=my\\040data  /srv/my\\040data  xfs  defaults,noexec,nodev  0 2
+/dev/sdc1         /backup         ext4    defaults,noauto   0 0
+/dev/sdd1         /scratch        ext4    defaults,noatime  0 2
+/swapfile         none            swap    sw                0 0
+nas:/export       /srv/nfs        nfs4    defaults          0 0
+",
+    ).get_entries();
+    assert_eq!(fstab.len(), 6);
+    assert_eq!(fstab[1].get_dev(), Path::new("LABEL=my data"));
+    assert_eq!(fstab[1].get_mnt(), Path::new("/srv/my data"));
+    assert_eq!(fstab[1].get_pass(), 2);
+
+    let live = Mounts::parse(
+        b"/dev/sda1 / ext4 rw,relatime,errors=remount-ro 0 0
+proc /proc proc rw,nosuid,nodev,noexec,relatime 0 0
+/dev/sdb1 /srv/my\\040data xfs rw,nodev,relatime 0 0
+/dev/sde1 /mnt/usb vfat rw,relatime 0 0
+nas:/export /srv/nfs nfs4 rw,relatime,vers=4.2 0 0
+/dev/loop3 /snap/core/1 squashfs ro,nodev,relatime 0 0
+portal /run/user/1000/doc fuse.portal rw,nosuid,nodev,relatime 0 0
+",
+    ).get_mounts();
+    let tree = TestTree::new("fstab");
+    for dev in &["dev/sda1", "dev/sdb1", "dev/sdc1"] {
+        tree.write(dev, "");
+    }
+    tree.symlink("../../sda1", "dev/disk/by-uuid/46bfd5e8-5d2c-4c1d-9f4b-0c6c7f0e9a11");
+    tree.symlink("../../sdc1", "dev/disk/by-label/my\\x20data");
+    let report = FstabReport::compare(&fstab, &live, tree.path());
+    assert!(!report.is_clean());
+    let missing: Vec<&Path> = report.get_missing().iter().map(|m| m.get_mnt()).collect();
+    assert_eq!(missing, vec![Path::new("/scratch")]);
+    let undeclared: Vec<&Path> = report.get_undeclared().iter().map(|m| m.get_mnt()).collect();
+    assert_eq!(undeclared, vec![Path::new("/mnt/usb")]);
+    assert_eq!(
+        report.get_mismatched(),
+        &vec![SourceMismatch {
+            mnt: PathBuf::from("/srv/my data"),
+            expected: PathBuf::from("LABEL=my data"),
+            live: PathBuf::from("/dev/sdb1"),
+        }]
+    );
+    assert_eq!(
+        report.get_drift(),
+        &vec![OptionDrift {
+            mnt: PathBuf::from("/srv/my data"),
+            expected: "noexec".to_string(),
+            live: "exec".to_string(),
+        }]
+    );
+}
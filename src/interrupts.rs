@@ -0,0 +1,452 @@
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+use std::io::Read;
+
+pub static INTERRUPTS: &str = "/proc/interrupts";
+pub static SOFTIRQS: &str = "/proc/softirqs";
+
+/// A row of /proc/interrupts. Numbered rows are hardware IRQs with a chip, hwirq and
+/// devices, the named rows such as NMI, LOC, RES and TLB are architecture counters with a
+/// description instead
+#[derive(Debug)]
+pub struct Interrupt {
+    name: String,
+    counts: Vec<u64>,
+    chip: Option<String>,
+    hwirq: Option<String>,
+    devices: Vec<String>,
+    description: Option<String>,
+}
+
+impl PartialEq for Interrupt {
+    fn eq(&self, other: &Interrupt) -> bool {
+        (self.name == other.name) & (self.counts == other.counts) & (self.chip == other.chip)
+            & (self.hwirq == other.hwirq) & (self.devices == other.devices)
+            & (self.description == other.description)
+    }
+}
+
+impl fmt::Display for Interrupt {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmtr, "IRQ {}: {} {}", self.name, self.total(), self.devices.join(", "))
+    }
+}
+
+impl Interrupt {
+    /// IRQ number or the name of a special row, e.g. "24" or "LOC"
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Counts in the order of Interrupts::get_cpus. ERR and MIS have a single count
+    pub fn get_counts(&self) -> &Vec<u64> {
+        &self.counts
+    }
+
+    /// Interrupt controller, e.g. IO-APIC or PCI-MSI
+    pub fn get_chip(&self) -> Option<&str> {
+        self.chip.as_deref()
+    }
+
+    /// Hardware IRQ number within the chip with its trigger type, e.g. "524288-edge"
+    pub fn get_hwirq(&self) -> Option<&str> {
+        self.hwirq.as_deref()
+    }
+
+    /// Drivers sharing the IRQ, e.g. ["eth0-TxRx-0"]
+    pub fn get_devices(&self) -> &Vec<String> {
+        &self.devices
+    }
+
+    /// Text of a special row, e.g. "Local timer interrupts"
+    pub fn get_description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// NMI, LOC, RES, TLB and the other architecture rows
+    pub fn is_special(&self) -> bool {
+        !self.name.chars().all(|c| c.is_ascii_digit())
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    fn parse(line: &str, ncpus: usize) -> Option<Interrupt> {
+        let i = line.find(':')?;
+        let name = line[..i].trim().to_string();
+        let mut rest = line[i + 1..].split_whitespace().peekable();
+        let mut counts = vec![];
+        while counts.len() < ncpus {
+            match rest.peek().and_then(|c| c.parse::<u64>().ok()) {
+                Some(c) => counts.push(c),
+                None => break,
+            }
+            rest.next();
+        }
+        let tail: Vec<&str> = rest.collect();
+        let mut irq = Interrupt {
+            name,
+            counts,
+            chip: None,
+            hwirq: None,
+            devices: vec![],
+            description: None,
+        };
+        if irq.is_special() {
+            if !tail.is_empty() {
+                irq.description = Some(tail.join(" "));
+            }
+            return Some(irq);
+        }
+        let mut tail = tail.into_iter().peekable();
+        irq.chip = tail.next().map(|c| c.to_string());
+        if tail.peek().is_some_and(|h| h.starts_with(|c: char| c.is_ascii_digit())) {
+            let mut hwirq = tail.next().unwrap().to_string();
+            // ARM GIC lists the trigger as a separate column
+            if tail.peek().is_some_and(|t| *t == "Level" || *t == "Edge") {
+                hwirq = format!("{}-{}", hwirq, tail.next().unwrap().to_lowercase());
+            }
+            irq.hwirq = Some(hwirq);
+        }
+        let devices = tail.collect::<Vec<&str>>().join(" ");
+        irq.devices = devices
+            .split(", ")
+            .filter(|d| !d.is_empty())
+            .map(|d| d.to_string())
+            .collect();
+        Some(irq)
+    }
+}
+
+#[derive(Debug)]
+pub struct Interrupts {
+    cpus: Vec<usize>,
+    interrupts: Vec<Interrupt>,
+}
+
+impl Interrupts {
+    pub fn get_interrupts(self) -> Vec<Interrupt> {
+        self.interrupts
+    }
+
+    /// Online CPUs, the columns of the table
+    pub fn get_cpus(&self) -> &Vec<usize> {
+        &self.cpus
+    }
+
+    pub fn new() -> Interrupts {
+        let data = fetch(INTERRUPTS);
+        Interrupts::parse(&data)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Interrupt> {
+        self.interrupts.iter().find(|i| i.name == name)
+    }
+
+    /// Every IRQ with a device whose name starts with prefix, e.g. all queues of "eth0"
+    pub fn for_device(&self, prefix: &str) -> Vec<&Interrupt> {
+        self.interrupts
+            .iter()
+            .filter(|i| i.devices.iter().any(|d| d.starts_with(prefix)))
+            .collect()
+    }
+
+    /// Per second rates for every IRQ present in both samples
+    pub fn rates(&self, prev: &Interrupts, secs: f64) -> Vec<IrqRate> {
+        let mut rates = vec![];
+        for irq in &self.interrupts {
+            if let Some(p) = prev.get(&irq.name) {
+                let now = (&self.cpus[..], &irq.counts[..]);
+                rates.push(IrqRate::new(&irq.name, now, (&prev.cpus, &p.counts), secs))
+            }
+        }
+        rates
+    }
+
+    /// Combined rate of every IRQ of a device, the per CPU split shows whether its queues
+    /// are spread out or all landing on one CPU
+    pub fn device_rate(&self, prev: &Interrupts, secs: f64, prefix: &str) -> IrqRate {
+        let names: Vec<&str> = self.for_device(prefix).iter().map(|i| i.get_name()).collect();
+        let rates: Vec<IrqRate> = self.rates(prev, secs)
+            .into_iter()
+            .filter(|r| names.contains(&r.get_name()))
+            .collect();
+        IrqRate::combine(prefix, &rates)
+    }
+
+    fn parse(s: &str) -> Interrupts {
+        let mut lines = s.lines();
+        let cpus = lines.next().map(parse_header).unwrap_or_default();
+        let interrupts = lines.filter_map(|l| Interrupt::parse(l, cpus.len())).collect();
+        Interrupts { cpus, interrupts }
+    }
+}
+
+/// A softirq type and how often each CPU has run it
+#[derive(Debug)]
+pub struct SoftIrq {
+    name: String,
+    counts: Vec<u64>,
+}
+
+impl PartialEq for SoftIrq {
+    fn eq(&self, other: &SoftIrq) -> bool {
+        (self.name == other.name) & (self.counts == other.counts)
+    }
+}
+
+impl SoftIrq {
+    /// HI, TIMER, NET_TX, NET_RX, BLOCK, IRQ_POLL, TASKLET, SCHED, HRTIMER or RCU
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_counts(&self) -> &Vec<u64> {
+        &self.counts
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+}
+
+#[derive(Debug)]
+pub struct SoftIrqs {
+    cpus: Vec<usize>,
+    softirqs: Vec<SoftIrq>,
+}
+
+impl SoftIrqs {
+    pub fn get_softirqs(self) -> Vec<SoftIrq> {
+        self.softirqs
+    }
+
+    pub fn get_cpus(&self) -> &Vec<usize> {
+        &self.cpus
+    }
+
+    pub fn new() -> SoftIrqs {
+        let data = fetch(SOFTIRQS);
+        SoftIrqs::parse(&data)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SoftIrq> {
+        self.softirqs.iter().find(|s| s.name == name)
+    }
+
+    /// Per second rates, NET_RX piling up on one CPU means RPS or RSS isn't spreading
+    /// packet processing
+    pub fn rates(&self, prev: &SoftIrqs, secs: f64) -> Vec<IrqRate> {
+        let mut rates = vec![];
+        for softirq in &self.softirqs {
+            if let Some(p) = prev.get(&softirq.name) {
+                rates.push(IrqRate::new(
+                    &softirq.name,
+                    (&self.cpus, &softirq.counts),
+                    (&prev.cpus, &p.counts),
+                    secs,
+                ))
+            }
+        }
+        rates
+    }
+
+    fn parse(s: &str) -> SoftIrqs {
+        let mut lines = s.lines();
+        let cpus = lines.next().map(parse_header).unwrap_or_default();
+        let mut softirqs = vec![];
+        for line in lines {
+            let i = match line.find(':') {
+                Some(i) => i,
+                None => continue,
+            };
+            softirqs.push(SoftIrq {
+                name: line[..i].trim().to_string(),
+                counts: line[i + 1..]
+                    .split_whitespace()
+                    .map(|c| c.parse::<u64>().unwrap_or(0))
+                    .collect(),
+            })
+        }
+        SoftIrqs { cpus, softirqs }
+    }
+}
+
+/// Interrupts per second on each CPU between two samples
+#[derive(Debug)]
+pub struct IrqRate {
+    name: String,
+    cpus: Vec<usize>,
+    per_cpu: Vec<f64>,
+}
+
+impl fmt::Display for IrqRate {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmtr, "{}: {:.1}/s", self.name, self.total())?;
+        if let Some((cpu, share)) = self.busiest() {
+            write!(fmtr, " Busiest: CPU{} {:.0}%", cpu, share * 100.0)?;
+        }
+        Ok(())
+    }
+}
+
+impl IrqRate {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_cpus(&self) -> &Vec<usize> {
+        &self.cpus
+    }
+
+    /// Rates in the order of get_cpus
+    pub fn get_per_cpu(&self) -> &Vec<f64> {
+        &self.per_cpu
+    }
+
+    pub fn total(&self) -> f64 {
+        self.per_cpu.iter().sum()
+    }
+
+    /// The CPU handling the most interrupts and its share of the total, None when there
+    /// were none
+    pub fn busiest(&self) -> Option<(usize, f64)> {
+        let total = self.total();
+        if total <= 0.0 {
+            return None;
+        }
+        self.per_cpu
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .map(|(i, r)| (self.cpus.get(i).cloned().unwrap_or(i), r / total))
+    }
+
+    /// True when at least min_rate interrupts a second arrive and one CPU takes more
+    /// than max_share (0.0 - 1.0) of them. Only meaningful on more than one CPU
+    pub fn is_imbalanced(&self, min_rate: f64, max_share: f64) -> bool {
+        if self.per_cpu.len() < 2 || self.total() < min_rate {
+            return false;
+        }
+        self.busiest().is_some_and(|(_, share)| share > max_share)
+    }
+
+    /// Sum several rates, e.g. the queues of one NIC
+    pub fn combine(name: &str, rates: &[IrqRate]) -> IrqRate {
+        let cpus = rates.first().map(|r| r.cpus.clone()).unwrap_or_default();
+        let mut per_cpu = vec![0.0; cpus.len()];
+        for rate in rates {
+            for (i, r) in rate.per_cpu.iter().enumerate() {
+                if let Some(t) = per_cpu.get_mut(i) {
+                    *t += r;
+                }
+            }
+        }
+        IrqRate {
+            name: name.to_string(),
+            cpus,
+            per_cpu,
+        }
+    }
+
+    /// Samples are the CPU ids from the header with the counts of each column. Columns
+    /// shift when a CPU goes offline or comes back, so counts are paired by CPU id. A CPU
+    /// missing from the earlier sample has no rate
+    fn new(name: &str, now: Sample, prev: Sample, secs: f64) -> IrqRate {
+        let (cpus, counts) = now;
+        let per_cpu = cpus
+            .iter()
+            .zip(counts)
+            .map(|(cpu, n)| {
+                let then = prev.0.iter().position(|c| c == cpu).and_then(|i| prev.1.get(i));
+                let d = then.map_or(0, |t| n.saturating_sub(*t));
+                if secs > 0.0 {
+                    d as f64 / secs
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        IrqRate {
+            name: name.to_string(),
+            cpus: cpus.to_vec(),
+            per_cpu,
+        }
+    }
+}
+
+/// CPU ids of the columns and one row's counts
+type Sample<'a> = (&'a [usize], &'a [u64]);
+
+/// The header names the CPU of each column, offline CPUs are left out
+fn parse_header(line: &str) -> Vec<usize> {
+    line.split_whitespace()
+        .filter_map(|c| c.trim_start_matches("CPU").parse::<usize>().ok())
+        .collect()
+}
+
+fn fetch(path: &str) -> String {
+    let path = Path::new(path);
+    let mut data = String::new();
+    let mut f = File::open(path).expect("Unable to open file");
+    f.read_to_string(&mut data).expect("Unable to read string");
+    data
+}
+
+#[test]
+fn test_interrupts_parse() {
+    let prev = Interrupts::parse(
+        "           CPU0       CPU1       CPU3
+  0:         22          0          0   IO-APIC   2-edge      timer
+  9:          0          0          0   IO-APIC   9-fasteoi   acpi, i801_smbus
+ 11:          5          0          0     GICv3  27 Level     arch_timer
+ 30:       1000          0          0   PCI-MSI 524288-edge      eth0-TxRx-0
+ 31:       1000          0          0   PCI-MSI 524289-edge      eth0-TxRx-1
+NMI:          0          0          0   Non-maskable interrupts
+LOC:     119298     100000      90000   Local timer interrupts
+ERR:          0
+",
+    );
+    assert_eq!(prev.get_cpus(), &vec![0, 1, 3]);
+    let acpi = prev.get("9").unwrap();
+    assert_eq!(acpi.get_devices(), &vec!["acpi", "i801_smbus"]);
+    assert_eq!(acpi.get_hwirq(), Some("9-fasteoi"));
+    assert_eq!(prev.get("11").unwrap().get_hwirq(), Some("27-level"));
+    let loc = prev.get("LOC").unwrap();
+    assert!(loc.is_special());
+    assert_eq!(loc.get_description(), Some("Local timer interrupts"));
+    assert_eq!(loc.total(), 309298);
+    assert_eq!(prev.get("ERR").unwrap().get_counts(), &vec![0]);
+    assert_eq!(prev.for_device("eth0").len(), 2);
+
+    let now = Interrupts::parse(
+        "           CPU0       CPU1       CPU3
+ 30:      21000         10          0   PCI-MSI 524288-edge      eth0-TxRx-0
+ 31:      20990          0          0   PCI-MSI 524289-edge      eth0-TxRx-1
+",
+    );
+    let nic = now.device_rate(&prev, 10.0, "eth0");
+    assert_eq!(nic.get_per_cpu(), &vec![3999.0, 1.0, 0.0]);
+    assert_eq!(nic.busiest().unwrap().0, 0);
+    assert!(nic.is_imbalanced(100.0, 0.9));
+
+    // CPU1 went offline and CPU2 came online, the CPU3 column moved
+    let now = Interrupts::parse(
+        "           CPU0       CPU2       CPU3
+LOC:     129298     150000      90500   Local timer interrupts
+",
+    );
+    let loc = &now.rates(&prev, 10.0)[0];
+    assert_eq!(loc.get_cpus(), &vec![0, 2, 3]);
+    assert_eq!(loc.get_per_cpu(), &vec![1000.0, 0.0, 50.0]);
+
+    let softirqs = SoftIrqs::parse(
+        "                    CPU0       CPU1
+          HI:          0          0
+      NET_RX:        500        700
+",
+    );
+    assert_eq!(softirqs.get("NET_RX").unwrap().total(), 1200);
+}
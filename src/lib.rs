@@ -1,8 +1,35 @@
 #![feature(i128_type)]
 
 extern crate regex;
+extern crate libc;
 
 pub mod process;
 pub mod network;
 pub mod uptime;
+pub mod loadavg;
+pub mod cpu;
+pub mod meminfo;
+pub mod slabinfo;
+pub mod numa;
+pub mod stat;
+pub mod interrupts;
 pub mod mounts;
+pub mod mountwatch;
+pub mod mountinfo;
+pub mod fstab;
+pub mod mountstats;
+pub mod overlay;
+pub mod blockid;
+pub mod diskstats;
+pub mod block;
+pub mod btrfs;
+pub mod zfs;
+pub mod mdstat;
+pub mod oom;
+pub mod vmstat;
+pub mod psi;
+pub mod swaps;
+mod sysfs;
+
+#[cfg(test)]
+mod testtree;
@@ -0,0 +1,117 @@
+use std::fs::File;
+use std::path::Path;
+use std::io::Read;
+use std::fmt;
+use cpu;
+
+pub static LOADAVG: &str = "/proc/loadavg";
+
+#[derive(Debug)]
+pub struct LoadAvg {
+    one: f64,
+    five: f64,
+    fifteen: f64,
+    runnable: u64,
+    entities: u64,
+    last_pid: i64,
+}
+
+impl PartialEq for LoadAvg {
+    fn eq(&self, other: &LoadAvg) -> bool {
+        (self.one == other.one) & (self.five == other.five) & (self.fifteen == other.fifteen)
+            & (self.runnable == other.runnable) & (self.entities == other.entities)
+            & (self.last_pid == other.last_pid)
+    }
+}
+
+impl fmt::Display for LoadAvg {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "Load average: {} {} {} Runnable: {}/{}",
+            self.one, self.five, self.fifteen, self.runnable, self.entities
+        )
+    }
+}
+
+impl LoadAvg {
+    pub fn new() -> LoadAvg {
+        let text = LoadAvg::fetch();
+        LoadAvg::parse(text)
+    }
+
+    pub fn get_one(&self) -> f64 {
+        self.one
+    }
+
+    pub fn get_five(&self) -> f64 {
+        self.five
+    }
+
+    pub fn get_fifteen(&self) -> f64 {
+        self.fifteen
+    }
+
+    /// Scheduling entities currently runnable
+    pub fn get_runnable(&self) -> u64 {
+        self.runnable
+    }
+
+    /// Scheduling entities (processes and threads) that exist on the system
+    pub fn get_entities(&self) -> u64 {
+        self.entities
+    }
+
+    /// PID of the most recently created process
+    pub fn get_last_pid(&self) -> i64 {
+        self.last_pid
+    }
+
+    /// Load averages divided by the online CPU count, so 1.0 means every CPU is busy
+    pub fn normalized(&self) -> (f64, f64, f64) {
+        self.normalize(cpu::online_cpus())
+    }
+
+    /// Load averages divided by the given CPU count
+    pub fn normalize(&self, cpus: usize) -> (f64, f64, f64) {
+        let cpus = cpus.max(1) as f64;
+        (self.one / cpus, self.five / cpus, self.fifteen / cpus)
+    }
+
+    fn fetch() -> String {
+        let path = Path::new(LOADAVG);
+        let mut data = String::new();
+        let mut f = File::open(path).expect("Unable to open file");
+        f.read_to_string(&mut data).expect("Unable to read string");
+        data
+    }
+
+    fn parse(load: String) -> LoadAvg {
+        let parse: Vec<&str> = load.split_whitespace().collect();
+        let sched: Vec<&str> = parse[3].split('/').collect();
+        LoadAvg {
+            one: parse[0].parse::<f64>().unwrap(),
+            five: parse[1].parse::<f64>().unwrap(),
+            fifteen: parse[2].parse::<f64>().unwrap(),
+            runnable: sched[0].parse::<u64>().unwrap(),
+            entities: sched[1].parse::<u64>().unwrap(),
+            last_pid: parse[4].parse::<i64>().unwrap(),
+        }
+    }
+}
+
+#[test]
+fn test_loadavg_parse() {
+    let loadavg = "1.72 0.98 0.64 3/1214 28573\n";
+    let l = LoadAvg {
+        one: 1.72,
+        five: 0.98,
+        fifteen: 0.64,
+        runnable: 3,
+        entities: 1214,
+        last_pid: 28573,
+    };
+    let test = LoadAvg::parse(loadavg.to_string());
+    assert_eq!(test, l);
+    assert_eq!(test.normalize(4), (0.43, 0.245, 0.16))
+}
@@ -0,0 +1,545 @@
+use std::fs;
+use std::fs::File;
+use std::path::Path;
+use std::io::Read;
+use std::fmt;
+use block::BlockDevices;
+use blockid::SYS_BLOCK;
+use mountinfo::MountInfo;
+use mounts::Mount;
+
+pub static MDSTAT: &str = "/proc/mdstat";
+
+/// State of an array member, from the flags after its slot number. A member can carry
+/// several, e.g. "(W)(F)" for a failed write-mostly member, the most serious one wins
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MemberState {
+    InSync,
+    Faulty,
+    Spare,
+    WriteMostly,
+    Replacement,
+    Journal,
+}
+
+#[derive(Debug)]
+pub struct MdMember {
+    name: String,
+    slot: u32,
+    state: MemberState,
+    write_mostly: bool,
+}
+
+impl PartialEq for MdMember {
+    fn eq(&self, other: &MdMember) -> bool {
+        (self.name == other.name) & (self.slot == other.slot) & (self.state == other.state)
+            & (self.write_mostly == other.write_mostly)
+    }
+}
+
+impl MdMember {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_slot(&self) -> u32 {
+        self.slot
+    }
+
+    pub fn get_state(&self) -> MemberState {
+        self.state
+    }
+
+    /// Flagged (W), kept even when the member has also failed or become a spare
+    pub fn is_write_mostly(&self) -> bool {
+        self.write_mostly
+    }
+
+    /// Parse a member such as "sdb1[1](F)" or "sdc1[2](W)(F)"
+    fn parse(s: &str) -> Option<MdMember> {
+        let open = s.find('[')?;
+        let close = s.find(']')?;
+        let flags = &s[close + 1..];
+        let state = if flags.contains("(F)") {
+            MemberState::Faulty
+        } else if flags.contains("(S)") {
+            MemberState::Spare
+        } else if flags.contains("(R)") {
+            MemberState::Replacement
+        } else if flags.contains("(J)") {
+            MemberState::Journal
+        } else if flags.contains("(W)") {
+            MemberState::WriteMostly
+        } else {
+            MemberState::InSync
+        };
+        Some(MdMember {
+            name: s[..open].to_string(),
+            slot: s[open + 1..close].parse::<u32>().ok()?,
+            state,
+            write_mostly: flags.contains("(W)"),
+        })
+    }
+}
+
+/// A resync, recovery, reshape or check in progress
+#[derive(Debug)]
+pub struct MdSync {
+    action: String,
+    percent: f64,
+    done: u64,
+    total: u64,
+    finish_minutes: f64,
+    speed_kb: u64,
+}
+
+impl PartialEq for MdSync {
+    fn eq(&self, other: &MdSync) -> bool {
+        (self.action == other.action) & (self.percent == other.percent)
+            & (self.done == other.done) & (self.total == other.total)
+            & (self.finish_minutes == other.finish_minutes)
+            & (self.speed_kb == other.speed_kb)
+    }
+}
+
+impl fmt::Display for MdSync {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "{} {}% finish={}min speed={}K/sec",
+            self.action, self.percent, self.finish_minutes, self.speed_kb
+        )
+    }
+}
+
+impl MdSync {
+    /// resync, recovery, reshape or check
+    pub fn get_action(&self) -> &str {
+        &self.action
+    }
+
+    pub fn get_percent(&self) -> f64 {
+        self.percent
+    }
+
+    /// Blocks done so far
+    pub fn get_done(&self) -> u64 {
+        self.done
+    }
+
+    pub fn get_total(&self) -> u64 {
+        self.total
+    }
+
+    /// Estimated minutes until the operation completes
+    pub fn get_finish_minutes(&self) -> f64 {
+        self.finish_minutes
+    }
+
+    pub fn get_speed_kb(&self) -> u64 {
+        self.speed_kb
+    }
+
+    /// Parse a progress line such as
+    /// "[==>....]  recovery = 12.6% (13213952/104792064) finish=16.3min speed=93501K/sec"
+    fn parse(line: &str) -> Option<MdSync> {
+        let eq = line.find(" = ")?;
+        let action = line[..eq].split_whitespace().last()?.to_string();
+        let mut sync = MdSync {
+            action,
+            percent: 0.0,
+            done: 0,
+            total: 0,
+            finish_minutes: 0.0,
+            speed_kb: 0,
+        };
+        for field in line[eq + 3..].split_whitespace() {
+            if field.ends_with('%') {
+                sync.percent = field.trim_end_matches('%').parse::<f64>().unwrap_or(0.0);
+            } else if field.starts_with('(') {
+                let counts: Vec<&str> = field.trim_matches(|c| c == '(' || c == ')')
+                    .split('/')
+                    .collect();
+                if counts.len() == 2 {
+                    sync.done = counts[0].parse::<u64>().unwrap_or(0);
+                    sync.total = counts[1].parse::<u64>().unwrap_or(0);
+                }
+            } else if let Some(finish) = field.strip_prefix("finish=") {
+                sync.finish_minutes = finish
+                    .trim_end_matches("min")
+                    .parse::<f64>()
+                    .unwrap_or(0.0);
+            } else if let Some(speed) = field.strip_prefix("speed=") {
+                sync.speed_kb = speed
+                    .trim_end_matches("K/sec")
+                    .parse::<u64>()
+                    .unwrap_or(0);
+            }
+        }
+        Some(sync)
+    }
+}
+
+#[derive(Debug)]
+pub struct MdArray {
+    name: String,
+    active: bool,
+    read_only: bool,
+    level: Option<String>,
+    members: Vec<MdMember>,
+    blocks: u64,
+    raid_disks: u32,
+    active_disks: u32,
+    status: String,
+    sync: Option<MdSync>,
+    array_state: Option<String>,
+    missing: Option<u32>,
+    mismatch_cnt: Option<u64>,
+}
+
+impl PartialEq for MdArray {
+    fn eq(&self, other: &MdArray) -> bool {
+        (self.name == other.name) & (self.active == other.active)
+            & (self.read_only == other.read_only) & (self.level == other.level)
+            & (self.members == other.members) & (self.blocks == other.blocks)
+            & (self.raid_disks == other.raid_disks)
+            & (self.active_disks == other.active_disks) & (self.status == other.status)
+            & (self.sync == other.sync) & (self.array_state == other.array_state)
+            & (self.missing == other.missing) & (self.mismatch_cnt == other.mismatch_cnt)
+    }
+}
+
+impl fmt::Display for MdArray {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "Array: {} Level: {} [{}/{}] [{}]{}",
+            self.name,
+            self.level.as_ref().map_or("-", |l| l.as_str()),
+            self.raid_disks,
+            self.active_disks,
+            self.status,
+            if self.is_degraded() { " degraded" } else { "" }
+        )
+    }
+}
+
+impl MdArray {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Active but read-only, including auto-read-only until the first write
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// RAID personality such as raid1 or raid5, None for inactive arrays
+    pub fn get_level(&self) -> Option<&str> {
+        self.level.as_deref()
+    }
+
+    pub fn get_members(&self) -> &Vec<MdMember> {
+        &self.members
+    }
+
+    /// Usable size in 1K blocks
+    pub fn get_blocks(&self) -> u64 {
+        self.blocks
+    }
+
+    /// Number of devices the array should have
+    pub fn get_raid_disks(&self) -> u32 {
+        self.raid_disks
+    }
+
+    /// Number of devices currently working
+    pub fn get_active_disks(&self) -> u32 {
+        self.active_disks
+    }
+
+    /// Per slot status, U for up and _ for missing
+    pub fn get_status(&self) -> &str {
+        &self.status
+    }
+
+    pub fn get_sync(&self) -> Option<&MdSync> {
+        self.sync.as_ref()
+    }
+
+    /// array_state from /sys/block/<md>/md, e.g. clean, active or degraded
+    pub fn get_array_state(&self) -> Option<&str> {
+        self.array_state.as_deref()
+    }
+
+    /// Number of devices missing according to /sys/block/<md>/md/degraded
+    pub fn get_missing(&self) -> Option<u32> {
+        self.missing
+    }
+
+    /// Sectors found inconsistent by the last check or repair
+    pub fn get_mismatch_cnt(&self) -> Option<u64> {
+        self.mismatch_cnt
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.active_disks < self.raid_disks
+            || self.status.contains('_')
+            || self.missing.is_some_and(|m| m > 0)
+            || self.members.iter().any(|m| m.state == MemberState::Faulty)
+    }
+
+    /// Mounts whose filesystem sits on this array, directly or through dm/LVM
+    pub fn mounts<'a>(&self, mounts: &'a [Mount], devices: &BlockDevices) -> Vec<&'a Mount> {
+        match MountInfo::pid("self") {
+            Ok(info) => self.mounts_in(mounts, devices, &info),
+            Err(_) => vec![],
+        }
+    }
+
+    /// mounts with mountinfo that was already read, e.g. when checking every array
+    pub fn mounts_in<'a>(
+        &self,
+        mounts: &'a [Mount],
+        devices: &BlockDevices,
+        info: &MountInfo,
+    ) -> Vec<&'a Mount> {
+        mounts
+            .iter()
+            .filter(|m| match m.device_number_in(info) {
+                Ok((major, minor)) => match devices.get_by_number(major, minor) {
+                    Some(d) => devices
+                        .lower_devices(d.get_name())
+                        .iter()
+                        .any(|l| l.get_name() == self.name),
+                    None => false,
+                },
+                Err(_) => false,
+            })
+            .collect()
+    }
+
+    fn read_sysfs(&mut self, sys_block: &Path) {
+        let md = sys_block.join(&self.name).join("md");
+        let attr = |a: &str| fs::read_to_string(md.join(a)).ok().map(|s| s.trim().to_string());
+        self.array_state = attr("array_state");
+        self.missing = attr("degraded").and_then(|d| d.parse::<u32>().ok());
+        self.mismatch_cnt = attr("mismatch_cnt").and_then(|m| m.parse::<u64>().ok());
+    }
+
+    fn parse(lines: &[&str]) -> Option<MdArray> {
+        let header: Vec<&str> = lines[0].split_whitespace().collect();
+        if header.len() < 3 || header[1] != ":" {
+            return None;
+        }
+        let mut array = MdArray {
+            name: header[0].to_string(),
+            active: header[2] == "active",
+            read_only: false,
+            level: None,
+            members: vec![],
+            blocks: 0,
+            raid_disks: 0,
+            active_disks: 0,
+            status: String::new(),
+            sync: None,
+            array_state: None,
+            missing: None,
+            mismatch_cnt: None,
+        };
+        for field in &header[3..] {
+            if *field == "(read-only)" || *field == "(auto-read-only)" {
+                array.read_only = true;
+            } else if field.contains('[') {
+                if let Some(m) = MdMember::parse(field) {
+                    array.members.push(m)
+                }
+            } else if array.active && array.level.is_none() {
+                array.level = Some(field.to_string());
+            }
+        }
+        for line in &lines[1..] {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if line.contains(" blocks") {
+                array.blocks = fields[0].parse::<u64>().unwrap_or(0);
+                for f in &fields {
+                    if f.starts_with('[') && f.contains('/') {
+                        let counts: Vec<&str> = f.trim_matches(|c| c == '[' || c == ']')
+                            .split('/')
+                            .collect();
+                        array.raid_disks = counts[0].parse::<u32>().unwrap_or(0);
+                        array.active_disks = counts[1].parse::<u32>().unwrap_or(0);
+                    } else if f.starts_with('[') && f.ends_with(']') {
+                        array.status = f.trim_matches(|c| c == '[' || c == ']').to_string();
+                    }
+                }
+            } else if line.contains(" = ") {
+                array.sync = MdSync::parse(line);
+            }
+        }
+        Some(array)
+    }
+}
+
+#[derive(Debug)]
+pub struct MdStat {
+    personalities: Vec<String>,
+    arrays: Vec<MdArray>,
+}
+
+impl MdStat {
+    pub fn get_arrays(self) -> Vec<MdArray> {
+        self.arrays
+    }
+
+    /// RAID levels the kernel has loaded
+    pub fn get_personalities(&self) -> &Vec<String> {
+        &self.personalities
+    }
+
+    pub fn new() -> MdStat {
+        let text = MdStat::fetch();
+        let mut md = MdStat::parse(text);
+        for array in &mut md.arrays {
+            array.read_sysfs(Path::new(SYS_BLOCK))
+        }
+        md
+    }
+
+    pub fn get(&self, name: &str) -> Option<&MdArray> {
+        self.arrays.iter().find(|a| a.name == name)
+    }
+
+    pub fn degraded(&self) -> Vec<&MdArray> {
+        self.arrays.iter().filter(|a| a.is_degraded()).collect()
+    }
+
+    fn fetch() -> String {
+        let path = Path::new(MDSTAT);
+        let mut data = String::new();
+        let mut f = File::open(path).expect("Unable to open file");
+        f.read_to_string(&mut data).expect("Unable to read string");
+        data
+    }
+
+    fn parse(s: String) -> MdStat {
+        let mut md = MdStat {
+            personalities: vec![],
+            arrays: vec![],
+        };
+        let lines: Vec<&str> = s.lines().collect();
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i];
+            if line.starts_with("Personalities") {
+                md.personalities = line.split_whitespace()
+                    .filter(|p| p.starts_with('['))
+                    .map(|p| p.trim_matches(|c| c == '[' || c == ']').to_string())
+                    .collect();
+                i += 1;
+            } else if !line.is_empty() && !line.starts_with(' ') && line.contains(" : ") {
+                // an array runs until the next blank or unindented line
+                let mut end = i + 1;
+                while end < lines.len() && lines[end].starts_with(' ') {
+                    end += 1;
+                }
+                if let Some(a) = MdArray::parse(&lines[i..end]) {
+                    md.arrays.push(a)
+                }
+                i = end;
+            } else {
+                i += 1;
+            }
+        }
+        md
+    }
+}
+
+#[test]
+fn test_mdstat_parse() {
+    let mdstat = "Personalities : [raid1] [raid6] [raid5] [raid4]
+md1 : active raid1 sdc2[2](W)(F) sdb2[1] sda2[0](W)
+      1953382400 blocks super 1.2 [2/2] [UU]
+      bitmap: 2/15 pages [8KB], 65536KB chunk
+
+md0 : active raid5 sdd1[3](S) sdc1[2] sdb1[1](F) sda1[0]
+      209584128 blocks super 1.2 level 5, 512k chunk, algorithm 2 [3/2] [U_U]
+      [==>..................]  recovery = 12.6% (13213952/104792064) finish=16.3min speed=93501K/sec
+
+md127 : inactive sdf1[0](S)
+      976630488 blocks super 1.2
+
+unused devices: <none>
+";
+    let md = MdStat::parse(mdstat.to_string());
+    assert_eq!(md.get_personalities().len(), 4);
+    let md1 = md.get("md1").unwrap();
+    assert_eq!(md1.get_level(), Some("raid1"));
+    assert_eq!(md1.get_members()[0].get_state(), MemberState::Faulty);
+    assert!(md1.get_members()[0].is_write_mostly());
+    assert_eq!(md1.get_members()[2].get_state(), MemberState::WriteMostly);
+    assert!(md1.is_degraded());
+    let md0 = md.get("md0").unwrap();
+    assert!(md0.is_degraded());
+    assert_eq!(md0.get_status(), "U_U");
+    assert_eq!(md0.get_members()[2].get_state(), MemberState::Faulty);
+    assert_eq!(md0.get_members()[0].get_state(), MemberState::Spare);
+    assert_eq!(
+        md0.get_sync(),
+        Some(&MdSync {
+            action: "recovery".to_string(),
+            percent: 12.6,
+            done: 13213952,
+            total: 104792064,
+            finish_minutes: 16.3,
+            speed_kb: 93501,
+        })
+    );
+    let md127 = md.get("md127").unwrap();
+    assert!(!md127.is_active());
+    assert_eq!(md127.get_level(), None);
+    assert_eq!(md.degraded().len(), 2)
+}
+
+#[test]
+fn test_mdstat_mounts() {
+    use mounts::Mounts;
+    use testtree::TestTree;
+
+    let tree = TestTree::new("mdstat");
+    tree.write_all(&[
+        ("md0/dev", "9:0"),
+        ("md0/md0p1/dev", "259:0"),
+        ("md0/md0p1/partition", "1"),
+        ("sda/dev", "8:0"),
+        ("sda/sda1/dev", "8:1"),
+        ("sda/sda1/partition", "1"),
+    ]);
+    tree.mkdir("md0/slaves/sda1");
+    let devices = BlockDevices::read(tree.path()).unwrap();
+    let info = MountInfo::parse(
+        b"22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw
+30 22 259:0 / /data rw,relatime shared:2 - xfs /dev/md0p1 rw
+31 22 0:40 / /tmp rw,nosuid shared:3 - tmpfs tmpfs rw
+",
+    ).unwrap();
+    let mounts = Mounts::parse(
+        b"/dev/sda1 / ext4 rw,relatime 0 0
+/dev/md0p1 /data xfs rw,relatime 0 0
+tmpfs /tmp tmpfs rw,nosuid 0 0
+",
+    ).get_mounts();
+    let md = MdStat::parse(
+        "md0 : active raid1 sda1[0]
+      104792064 blocks super 1.2 [2/1] [U_]
+"
+        .to_string(),
+    );
+    let affected = md.get("md0").unwrap().mounts_in(&mounts, &devices, &info);
+    assert_eq!(affected.len(), 1);
+    assert_eq!(affected[0].get_mnt(), Path::new("/data"))
+}
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use std::io::Read;
+use std::fmt;
+
+pub static MEMINFO: &str = "/proc/meminfo";
+
+/// System wide memory usage, all sizes are in kB apart from the HugePages_* counts which
+/// are in pages
+#[derive(Debug)]
+pub struct MemInfo {
+    mem_total: u64,
+    mem_free: u64,
+    mem_available: Option<u64>,
+    buffers: u64,
+    cached: u64,
+    swap_total: u64,
+    swap_free: u64,
+    dirty: u64,
+    writeback: u64,
+    slab: u64,
+    s_reclaimable: u64,
+    shmem: u64,
+    huge_pages_total: u64,
+    huge_pages_free: u64,
+    huge_pages_rsvd: u64,
+    huge_pages_surp: u64,
+    huge_page_size: u64,
+    commit_limit: u64,
+    committed_as: u64,
+    other: HashMap<String, u64>,
+}
+
+impl PartialEq for MemInfo {
+    fn eq(&self, other: &MemInfo) -> bool {
+        (self.mem_total == other.mem_total) & (self.mem_free == other.mem_free)
+            & (self.mem_available == other.mem_available) & (self.buffers == other.buffers)
+            & (self.cached == other.cached) & (self.swap_total == other.swap_total)
+            & (self.swap_free == other.swap_free) & (self.dirty == other.dirty)
+            & (self.writeback == other.writeback) & (self.slab == other.slab)
+            & (self.s_reclaimable == other.s_reclaimable) & (self.shmem == other.shmem)
+            & (self.huge_pages_total == other.huge_pages_total)
+            & (self.huge_pages_free == other.huge_pages_free)
+            & (self.huge_pages_rsvd == other.huge_pages_rsvd)
+            & (self.huge_pages_surp == other.huge_pages_surp)
+            & (self.huge_page_size == other.huge_page_size)
+            & (self.commit_limit == other.commit_limit)
+            & (self.committed_as == other.committed_as) & (self.other == other.other)
+    }
+}
+
+impl fmt::Display for MemInfo {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "Total: {} kB Available: {} kB Used: {:.1}% Swap: {}/{} kB",
+            self.mem_total,
+            self.available(),
+            self.used_percent(),
+            self.swap_total - self.swap_free,
+            self.swap_total
+        )
+    }
+}
+
+impl MemInfo {
+    pub fn new() -> MemInfo {
+        let text = MemInfo::fetch();
+        MemInfo::parse(text)
+    }
+
+    pub fn get_mem_total(&self) -> u64 {
+        self.mem_total
+    }
+
+    pub fn get_mem_free(&self) -> u64 {
+        self.mem_free
+    }
+
+    /// MemAvailable as reported by the kernel, None before Linux 3.14
+    pub fn get_mem_available(&self) -> Option<u64> {
+        self.mem_available
+    }
+
+    pub fn get_buffers(&self) -> u64 {
+        self.buffers
+    }
+
+    pub fn get_cached(&self) -> u64 {
+        self.cached
+    }
+
+    pub fn get_swap_total(&self) -> u64 {
+        self.swap_total
+    }
+
+    pub fn get_swap_free(&self) -> u64 {
+        self.swap_free
+    }
+
+    pub fn get_dirty(&self) -> u64 {
+        self.dirty
+    }
+
+    pub fn get_writeback(&self) -> u64 {
+        self.writeback
+    }
+
+    pub fn get_slab(&self) -> u64 {
+        self.slab
+    }
+
+    pub fn get_s_reclaimable(&self) -> u64 {
+        self.s_reclaimable
+    }
+
+    pub fn get_shmem(&self) -> u64 {
+        self.shmem
+    }
+
+    pub fn get_huge_pages_total(&self) -> u64 {
+        self.huge_pages_total
+    }
+
+    pub fn get_huge_pages_free(&self) -> u64 {
+        self.huge_pages_free
+    }
+
+    pub fn get_huge_pages_rsvd(&self) -> u64 {
+        self.huge_pages_rsvd
+    }
+
+    pub fn get_huge_pages_surp(&self) -> u64 {
+        self.huge_pages_surp
+    }
+
+    pub fn get_huge_page_size(&self) -> u64 {
+        self.huge_page_size
+    }
+
+    pub fn get_commit_limit(&self) -> u64 {
+        self.commit_limit
+    }
+
+    pub fn get_committed_as(&self) -> u64 {
+        self.committed_as
+    }
+
+    /// Any key without a dedicated field, e.g. "AnonPages" or "DirectMap2M"
+    pub fn get(&self, key: &str) -> Option<u64> {
+        self.other.get(key).cloned()
+    }
+
+    /// Memory available for new workloads without swapping. Kernels older than 3.14 don't
+    /// export MemAvailable, there it is estimated as free memory plus the page cache and
+    /// reclaimable slab, less shmem which lives in the page cache but can't be dropped
+    pub fn available(&self) -> u64 {
+        match self.mem_available {
+            Some(a) => a,
+            None => (self.mem_free + self.buffers + self.cached + self.s_reclaimable)
+                .saturating_sub(self.shmem),
+        }
+    }
+
+    /// Percentage of memory in use, counting reclaimable cache as free
+    pub fn used_percent(&self) -> f64 {
+        if self.mem_total == 0 {
+            return 0.0;
+        }
+        let used = self.mem_total.saturating_sub(self.available());
+        used as f64 / self.mem_total as f64 * 100.0
+    }
+
+    /// Committed_AS over CommitLimit, above 1.0 the system has promised more memory than
+    /// strict overcommit would allow
+    pub fn commit_ratio(&self) -> f64 {
+        if self.commit_limit == 0 {
+            return 0.0;
+        }
+        self.committed_as as f64 / self.commit_limit as f64
+    }
+
+    fn fetch() -> String {
+        let path = Path::new(MEMINFO);
+        let mut data = String::new();
+        let mut f = File::open(path).expect("Unable to open file");
+        f.read_to_string(&mut data).expect("Unable to read string");
+        data
+    }
+
+    fn parse(mi: String) -> MemInfo {
+        let mut values: HashMap<String, u64> = HashMap::new();
+        for line in mi.lines() {
+            let split: Vec<&str> = line.split_whitespace().collect();
+            if split.len() < 2 {
+                continue;
+            }
+            let key = split[0].trim_end_matches(':').to_string();
+            values.insert(key, split[1].parse::<u64>().unwrap());
+        }
+        let mem_available = values.remove("MemAvailable");
+        let mut take = |key: &str| values.remove(key).unwrap_or(0);
+        MemInfo {
+            mem_total: take("MemTotal"),
+            mem_free: take("MemFree"),
+            mem_available,
+            buffers: take("Buffers"),
+            cached: take("Cached"),
+            swap_total: take("SwapTotal"),
+            swap_free: take("SwapFree"),
+            dirty: take("Dirty"),
+            writeback: take("Writeback"),
+            slab: take("Slab"),
+            s_reclaimable: take("SReclaimable"),
+            shmem: take("Shmem"),
+            huge_pages_total: take("HugePages_Total"),
+            huge_pages_free: take("HugePages_Free"),
+            huge_pages_rsvd: take("HugePages_Rsvd"),
+            huge_pages_surp: take("HugePages_Surp"),
+            huge_page_size: take("Hugepagesize"),
+            commit_limit: take("CommitLimit"),
+            committed_as: take("Committed_AS"),
+            other: values,
+        }
+    }
+}
+
+#[test]
+fn test_meminfo_parse() {
+    let meminfo = "MemTotal:       16303764 kB
+MemFree:         1263384 kB
+MemAvailable:    9587164 kB
+Buffers:          842972 kB
+Cached:          7663116 kB
+SwapCached:         2548 kB
+SwapTotal:       8388604 kB
+SwapFree:        8311036 kB
+Dirty:               412 kB
+Writeback:             0 kB
+Shmem:            603168 kB
+Slab:             829088 kB
+SReclaimable:     638092 kB
+CommitLimit:    16540484 kB
+Committed_AS:   12711840 kB
+HugePages_Total:       0
+HugePages_Free:        0
+HugePages_Rsvd:        0
+HugePages_Surp:        0
+Hugepagesize:       2048 kB
+";
+    let m = MemInfo::parse(meminfo.to_string());
+    assert_eq!(m.get_mem_available(), Some(9587164));
+    assert_eq!(m.get("SwapCached"), Some(2548));
+    assert_eq!(m.get_huge_page_size(), 2048);
+    assert_eq!(format!("{:.2}", m.used_percent()), "41.20");
+    assert_eq!(format!("{:.3}", m.commit_ratio()), "0.769");
+
+    let old_kernel = "MemTotal: 1000 kB\nMemFree: 100 kB\nBuffers: 50 kB\nCached: 400 kB\nShmem: 50 kB\nSReclaimable: 20 kB\n";
+    let m = MemInfo::parse(old_kernel.to_string());
+    assert_eq!(m.get_mem_available(), None);
+    assert_eq!(m.available(), 520)
+}
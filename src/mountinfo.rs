@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use mounts::{unescape, MountOptions};
+
+pub static MOUNTINFO: &str = "/proc/self/mountinfo";
+
+/// Mount propagation from the optional fields of a mountinfo line
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Propagation {
+    /// shared:X, events propagate within peer group X
+    Shared(u32),
+    /// master:X, receives events from peer group X
+    Master(u32),
+    /// propagate_from:X, closest dominant peer group when X isn't reachable from this
+    /// mount namespace
+    PropagateFrom(u32),
+    /// unbindable, the mount can't be bind mounted
+    Unbindable,
+}
+
+impl Propagation {
+    fn parse(s: &str) -> Option<Propagation> {
+        let mut split = s.splitn(2, ':');
+        let tag = split.next().unwrap_or("");
+        let id = split.next().and_then(|i| i.parse::<u32>().ok());
+        match (tag, id) {
+            ("shared", Some(i)) => Some(Propagation::Shared(i)),
+            ("master", Some(i)) => Some(Propagation::Master(i)),
+            ("propagate_from", Some(i)) => Some(Propagation::PropagateFrom(i)),
+            ("unbindable", _) => Some(Propagation::Unbindable),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct MountInfoEntry {
+    mount_id: u32,
+    parent_id: u32,
+    major: u32,
+    minor: u32,
+    root: PathBuf,
+    mount_point: PathBuf,
+    options: MountOptions,
+    propagation: Vec<Propagation>,
+    fs_type: String,
+    source: PathBuf,
+    super_options: MountOptions,
+}
+
+impl PartialEq for MountInfoEntry {
+    fn eq(&self, other: &MountInfoEntry) -> bool {
+        (self.mount_id == other.mount_id) & (self.parent_id == other.parent_id)
+            & (self.major == other.major) & (self.minor == other.minor)
+            & (self.root == other.root) & (self.mount_point == other.mount_point)
+            & (self.options == other.options) & (self.propagation == other.propagation)
+            & (self.fs_type == other.fs_type) & (self.source == other.source)
+            & (self.super_options == other.super_options)
+    }
+}
+
+impl fmt::Display for MountInfoEntry {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "ID: {} Parent: {} Device: {}:{} Root: {} Mount: {}",
+            self.mount_id,
+            self.parent_id,
+            self.major,
+            self.minor,
+            self.root.display(),
+            self.mount_point.display()
+        )
+    }
+}
+
+impl MountInfoEntry {
+    /// Unique ID for the mount, may be reused after umount
+    pub fn get_mount_id(&self) -> u32 {
+        self.mount_id
+    }
+
+    /// ID of the parent mount, or of itself for the root of the mount namespace
+    pub fn get_parent_id(&self) -> u32 {
+        self.parent_id
+    }
+
+    pub fn get_major(&self) -> u32 {
+        self.major
+    }
+
+    pub fn get_minor(&self) -> u32 {
+        self.minor
+    }
+
+    /// Directory within the filesystem which forms the root of this mount, anything
+    /// other than "/" is a bind mount of a subdirectory
+    pub fn get_root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn get_mount_point(&self) -> &Path {
+        &self.mount_point
+    }
+
+    /// Per mount options
+    pub fn get_options(&self) -> &MountOptions {
+        &self.options
+    }
+
+    pub fn get_propagation(&self) -> &Vec<Propagation> {
+        &self.propagation
+    }
+
+    pub fn get_fs_type(&self) -> &str {
+        &self.fs_type
+    }
+
+    pub fn get_source(&self) -> &Path {
+        &self.source
+    }
+
+    /// Per super block options, shared by every mount of the filesystem
+    pub fn get_super_options(&self) -> &MountOptions {
+        &self.super_options
+    }
+
+    /// Private mounts have no propagation fields at all
+    pub fn is_private(&self) -> bool {
+        self.propagation.is_empty()
+    }
+
+    pub fn is_shared(&self) -> bool {
+        self.propagation
+            .iter()
+            .any(|p| matches!(*p, Propagation::Shared(_)))
+    }
+
+    fn parse(line: &[u8]) -> Result<MountInfoEntry, String> {
+        let fields: Vec<&[u8]> = line.split(|b| *b == b' ')
+            .filter(|f| !f.is_empty())
+            .collect();
+        let text: Vec<String> = fields
+            .iter()
+            .map(|f| String::from_utf8_lossy(f).into_owned())
+            .collect();
+        // the optional fields are variable length and end with a lone "-"
+        let sep = match text.iter().position(|f| f == "-") {
+            Some(i) if i >= 6 && text.len() >= i + 3 => i,
+            _ => return Err(format!("Failed to parse mountinfo line {}", text.join(" "))),
+        };
+        let dev: Vec<&str> = text[2].split(':').collect();
+        if dev.len() != 2 {
+            return Err(format!("Failed to parse device {}", text[2]));
+        }
+        let num = |s: &str| {
+            s.parse::<u32>()
+                .map_err(|e| format!("Failed to parse {}: {}", s, e))
+        };
+        Ok(MountInfoEntry {
+            mount_id: num(&text[0])?,
+            parent_id: num(&text[1])?,
+            major: num(dev[0])?,
+            minor: num(dev[1])?,
+            root: unescape(fields[3]),
+            mount_point: unescape(fields[4]),
+            options: MountOptions::parse(&text[5]),
+            propagation: text[6..sep]
+                .iter()
+                .filter_map(|p| Propagation::parse(p))
+                .collect(),
+            fs_type: text[sep + 1].clone(),
+            source: unescape(fields[sep + 2]),
+            super_options: MountOptions::parse(text.get(sep + 3).map_or("", |o| o.as_str())),
+        })
+    }
+}
+
+/// Mount table of a mount namespace from /proc/<pid>/mountinfo
+#[derive(Debug)]
+pub struct MountInfo {
+    entries: Vec<MountInfoEntry>,
+}
+
+impl MountInfo {
+    pub fn get_entries(self) -> Vec<MountInfoEntry> {
+        self.entries
+    }
+
+    /// Mounts in the namespace of the calling process
+    pub fn new() -> MountInfo {
+        let s = MountInfo::fetch(MOUNTINFO).unwrap();
+        MountInfo::parse(&s).unwrap()
+    }
+
+    /// Mounts in the namespace of another process
+    pub fn pid(pid: &str) -> Result<MountInfo, io::Error> {
+        let s = MountInfo::fetch(&format!("/proc/{}/mountinfo", pid))?;
+        MountInfo::parse(&s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn get(&self, mount_id: u32) -> Option<&MountInfoEntry> {
+        self.entries.iter().find(|e| e.mount_id == mount_id)
+    }
+
+    /// Parent of a mount, None for the namespace root
+    pub fn parent(&self, entry: &MountInfoEntry) -> Option<&MountInfoEntry> {
+        if entry.parent_id == entry.mount_id {
+            return None;
+        }
+        self.get(entry.parent_id)
+    }
+
+    /// The mount visible at a mount point, the last one when several are stacked on it
+    pub fn for_mount_point(&self, mount_point: &Path) -> Option<&MountInfoEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|e| e.mount_point == mount_point)
+    }
+
+    /// Mounts directly beneath a mount
+    pub fn children(&self, mount_id: u32) -> Vec<&MountInfoEntry> {
+        self.entries
+            .iter()
+            .filter(|e| e.parent_id == mount_id && e.mount_id != mount_id)
+            .collect()
+    }
+
+    /// Mounts whose parent isn't visible, normally just "/" but chroots and containers can
+    /// have more
+    pub fn roots(&self) -> Vec<&MountInfoEntry> {
+        self.entries
+            .iter()
+            .filter(|e| self.parent(e).is_none())
+            .collect()
+    }
+
+    /// Mounts grouped by major:minor where the same device is mounted more than once,
+    /// which is how bind mounts show up
+    pub fn bind_mounts(&self) -> Vec<Vec<&MountInfoEntry>> {
+        let mut devices: HashMap<(u32, u32), Vec<&MountInfoEntry>> = HashMap::new();
+        let mut order = vec![];
+        for e in &self.entries {
+            let key = (e.major, e.minor);
+            if !devices.contains_key(&key) {
+                order.push(key);
+            }
+            devices.entry(key).or_default().push(e);
+        }
+        order
+            .iter()
+            .filter_map(|k| devices.remove(k))
+            .filter(|v| v.len() > 1)
+            .collect()
+    }
+
+    /// Read as bytes, like /proc/mounts the paths may not be UTF-8
+    fn fetch(path: &str) -> Result<Vec<u8>, io::Error> {
+        let mut s = vec![];
+        let mut f = fs::File::open(path)?;
+        f.read_to_end(&mut s)?;
+        Ok(s)
+    }
+
+    pub(crate) fn parse(s: &[u8]) -> Result<MountInfo, String> {
+        let mut info = MountInfo { entries: vec![] };
+        for line in s.split(|b| *b == b'\n') {
+            if line.is_empty() {
+                continue;
+            }
+            info.entries.push(MountInfoEntry::parse(line)?)
+        }
+        Ok(info)
+    }
+}
+
+#[test]
+fn test_mountinfo_parse() {
+    let mountinfo = "22 1 253:0 / / rw,relatime shared:1 - xfs /dev/mapper/root rw,seclabel,attr2,inode64,noquota
+36 22 0:20 / /sys rw,nosuid,nodev,noexec,relatime shared:2 - sysfs sysfs rw,seclabel
+90 22 259:2 / /boot rw,relatime shared:51 - ext4 /dev/nvme0n1p2 rw,seclabel
+412 22 253:0 /var/lib/docker /srv/my\\040docker rw,relatime master:1 propagate_from:3 - xfs /dev/mapper/root rw,seclabel,attr2
+413 36 0:33 / /sys/fs/bpf rw,nosuid,nodev,noexec,relatime unbindable - bpf bpf rw,mode=700
+";
+    let info = MountInfo::parse(mountinfo.as_bytes()).unwrap();
+    let docker = info.get(412).unwrap();
+    assert_eq!(docker.get_root(), Path::new("/var/lib/docker"));
+    assert_eq!(docker.get_mount_point(), Path::new("/srv/my docker"));
+    assert_eq!(docker.get_major(), 253);
+    assert_eq!(
+        docker.get_propagation(),
+        &vec![Propagation::Master(1), Propagation::PropagateFrom(3)]
+    );
+    assert_eq!(docker.get_super_options().get_flags(), &vec!["rw", "seclabel", "attr2"]);
+    assert_eq!(info.get(413).unwrap().get_propagation(), &vec![Propagation::Unbindable]);
+    assert_eq!(info.roots().len(), 1);
+    assert_eq!(info.children(22).len(), 3);
+    assert_eq!(
+        info.parent(info.get(413).unwrap()).unwrap().get_mount_point(),
+        Path::new("/sys")
+    );
+    let binds = info.bind_mounts();
+    assert_eq!(binds.len(), 1);
+    assert_eq!(binds[0][1].get_source(), Path::new("/dev/mapper/root"))
+}
@@ -1,67 +1,462 @@
+use std::collections::HashMap;
 use std::fs;
 use std::str;
 use std::io::{BufReader, Read};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::fmt;
+use std::io;
+use std::ffi::{CString, OsStr};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::mem;
+use libc;
+use blockid::{BlockIdentity, BlockIdentityError};
+use btrfs::BtrfsFs;
+use mountinfo::{MountInfo, MOUNTINFO};
+use zfs::ZfsPool;
 
 pub static MOUNTS: &str = "/proc/mounts";
 
+/// Filesystems that don't store data on a device
+pub static PSEUDO_FS: &[&str] = &[
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devpts",
+    "efivarfs",
+    "fusectl",
+    "hugetlbfs",
+    "mqueue",
+    "nsfs",
+    "proc",
+    "pstore",
+    "rpc_pipefs",
+    "securityfs",
+    "selinuxfs",
+    "sysfs",
+    "tracefs",
+];
+
+/// Memory backed filesystems, real enough to fill up but not backed by a device
+pub static TMP_FS: &[&str] = &["tmpfs", "devtmpfs", "ramfs"];
+
 #[derive(Debug)]
 pub struct Mounts {
     mounts: Vec<Mount>,
 }
 
-#[derive(Debug)]
+/// Undo the octal escaping the kernel applies to space (\040), tab (\011), newline
+/// (\012) and backslash (\134) in mount table paths. Other bytes are passed through
+/// untouched so non UTF-8 paths survive.
+pub fn unescape(field: &[u8]) -> PathBuf {
+    let mut out = Vec::with_capacity(field.len());
+    let mut i = 0;
+    while i < field.len() {
+        if field[i] == b'\\' && i + 3 < field.len() && is_octal(&field[i + 1..i + 4]) {
+            let v = field[i + 1..i + 4]
+                .iter()
+                .fold(0u32, |acc, d| acc * 8 + (d - b'0') as u32);
+            out.push(v as u8);
+            i += 4;
+        } else {
+            out.push(field[i]);
+            i += 1;
+        }
+    }
+    PathBuf::from(OsStr::from_bytes(&out))
+}
+
+/// Split a dev_t into major and minor numbers using the glibc encoding
+pub fn split_dev(dev: u64) -> (u32, u32) {
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+    let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+    (major as u32, minor as u32)
+}
+
+fn is_octal(digits: &[u8]) -> bool {
+    digits.len() == 3 && digits[0] <= b'3' && digits.iter().all(|d| *d >= b'0' && *d <= b'7')
+}
+
+#[derive(Debug, Clone)]
 pub struct Mount {
-    dev: String,
-    mnt: String,
+    dev: PathBuf,
+    mnt: PathBuf,
     fs_type: String,
-    attrs: String,
-    dummy: Vec<String>,
+    options: MountOptions,
+    dump: u32,
+    pass: u32,
+}
+
+/// How access times are updated on a mount
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Atime {
+    /// Update atime on every access
+    Strictatime,
+    /// Only update atime when it is older than mtime/ctime or a day old
+    Relatime,
+    /// Never update atime
+    Noatime,
+}
+
+/// Mount options split into bare flags such as "nosuid" and key=value options such as
+/// "size=8073728k"
+#[derive(Debug, Clone)]
+pub struct MountOptions {
+    flags: Vec<String>,
+    values: HashMap<String, String>,
+    raw: HashMap<String, Vec<u8>>,
+}
+
+impl PartialEq for MountOptions {
+    fn eq(&self, other: &MountOptions) -> bool {
+        (self.flags == other.flags) & (self.values == other.values)
+    }
+}
+
+impl fmt::Display for MountOptions {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        let mut opts = self.flags.clone();
+        let mut values: Vec<String> = self.values
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect();
+        values.sort();
+        opts.extend(values);
+        write!(fmtr, "{}", opts.join(","))
+    }
+}
+
+impl MountOptions {
+    pub fn parse(s: &str) -> MountOptions {
+        MountOptions::parse_bytes(s.as_bytes())
+    }
+
+    /// Options as the kernel prints them, values keep their bytes for get_raw since paths
+    /// such as overlay's lowerdir need not be UTF-8
+    pub(crate) fn parse_bytes(s: &[u8]) -> MountOptions {
+        let mut opts = MountOptions {
+            flags: vec![],
+            values: HashMap::new(),
+            raw: HashMap::new(),
+        };
+        for opt in s.split(|b| *b == b',') {
+            if opt.is_empty() {
+                continue;
+            }
+            match opt.iter().position(|b| *b == b'=') {
+                Some(i) => {
+                    let key = String::from_utf8_lossy(&opt[..i]).into_owned();
+                    let value = &opt[i + 1..];
+                    opts.values
+                        .insert(key.clone(), String::from_utf8_lossy(value).into_owned());
+                    opts.raw.insert(key, value.to_vec());
+                }
+                None => opts.flags.push(String::from_utf8_lossy(opt).into_owned()),
+            }
+        }
+        opts
+    }
+
+    /// Bare flags in the order they were listed
+    pub fn get_flags(&self) -> &Vec<String> {
+        &self.flags
+    }
+
+    /// key=value options
+    pub fn get_values(&self) -> &HashMap<String, String> {
+        &self.values
+    }
+
+    pub fn has(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+
+    /// Value of a key=value option, e.g. get("errors") gives "remount-ro"
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|v| v.as_str())
+    }
+
+    /// Value of a key=value option as the bytes the kernel printed, still escaped
+    pub fn get_raw(&self, key: &str) -> Option<&OsStr> {
+        self.raw.get(key).map(|v| OsStr::from_bytes(v))
+    }
+
+    /// The kernel lists rw or ro first but fstab may put it anywhere, the last one wins
+    pub fn is_read_only(&self) -> bool {
+        self.flags
+            .iter()
+            .rev()
+            .find(|f| *f == "ro" || *f == "rw")
+            == Some(&"ro".to_string())
+    }
+
+    pub fn is_nosuid(&self) -> bool {
+        self.has("nosuid")
+    }
+
+    pub fn is_nodev(&self) -> bool {
+        self.has("nodev")
+    }
+
+    pub fn is_noexec(&self) -> bool {
+        self.has("noexec")
+    }
+
+    pub fn is_sync(&self) -> bool {
+        self.has("sync")
+    }
+
+    /// /proc/mounts lists relatime or noatime and shows nothing for strictatime
+    pub fn atime(&self) -> Atime {
+        if self.has("noatime") {
+            Atime::Noatime
+        } else if self.has("relatime") {
+            Atime::Relatime
+        } else {
+            Atime::Strictatime
+        }
+    }
+}
+
+/// Capacity of a mounted filesystem as reported by statvfs
+#[derive(Debug)]
+pub struct MountUsage {
+    block_size: u64,
+    total: u64,
+    free: u64,
+    available: u64,
+    inodes: u64,
+    inodes_free: u64,
+}
+
+impl PartialEq for MountUsage {
+    fn eq(&self, other: &MountUsage) -> bool {
+        (self.block_size == other.block_size) & (self.total == other.total)
+            & (self.free == other.free) & (self.available == other.available)
+            & (self.inodes == other.inodes) & (self.inodes_free == other.inodes_free)
+    }
+}
+
+impl fmt::Display for MountUsage {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "Size: {} Used: {} Avail: {} Use%: {}",
+            self.total,
+            self.used(),
+            self.available,
+            self.used_percent()
+        )
+    }
+}
+
+impl MountUsage {
+    /// Fundamental block size in bytes
+    pub fn get_block_size(&self) -> u64 {
+        self.block_size
+    }
+
+    /// Size of the filesystem in bytes
+    pub fn get_total(&self) -> u64 {
+        self.total
+    }
+
+    /// Free bytes, including those reserved for root
+    pub fn get_free(&self) -> u64 {
+        self.free
+    }
+
+    /// Free bytes available to unprivileged users
+    pub fn get_available(&self) -> u64 {
+        self.available
+    }
+
+    pub fn get_inodes(&self) -> u64 {
+        self.inodes
+    }
+
+    pub fn get_inodes_free(&self) -> u64 {
+        self.inodes_free
+    }
+
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.free)
+    }
+
+    /// Used percentage the way df calculates it, blocks reserved for root are left out
+    /// of the total and the result is rounded up
+    pub fn used_percent(&self) -> u64 {
+        let used = self.used();
+        let usable = used + self.available;
+        if usable == 0 {
+            return 0;
+        }
+        (used * 100).div_ceil(usable)
+    }
+
+    /// Used inode percentage rounded up, 0 for filesystems without fixed inode tables
+    pub fn inodes_used_percent(&self) -> u64 {
+        if self.inodes == 0 {
+            return 0;
+        }
+        let used = self.inodes.saturating_sub(self.inodes_free);
+        (used * 100).div_ceil(self.inodes)
+    }
+
+    pub(crate) fn statvfs(path: &Path) -> Result<MountUsage, io::Error> {
+        let c_path = match CString::new(path.as_os_str().as_bytes()) {
+            Ok(p) => p,
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidInput, e)),
+        };
+        let mut st: libc::statvfs = unsafe { mem::zeroed() };
+        if unsafe { libc::statvfs(c_path.as_ptr(), &mut st) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let frsize = st.f_frsize as u64;
+        Ok(MountUsage {
+            block_size: frsize,
+            total: st.f_blocks as u64 * frsize,
+            free: st.f_bfree as u64 * frsize,
+            available: st.f_bavail as u64 * frsize,
+            inodes: st.f_files as u64,
+            inodes_free: st.f_ffree as u64,
+        })
+    }
 }
 
 impl Mount {
+    /// Capacity and inode usage of the mounted filesystem
+    pub fn usage(&self) -> Result<MountUsage, io::Error> {
+        MountUsage::statvfs(&self.mnt)
+    }
+
+    /// Block group allocation for btrfs mounts, where statvfs free space can't be trusted
+    pub fn btrfs(&self) -> Option<BtrfsFs> {
+        BtrfsFs::for_mount(self)
+    }
+
+    /// Pool state for zfs datasets
+    pub fn zfs_pool(&self) -> Option<ZfsPool> {
+        ZfsPool::for_mount(self)
+    }
+
+    /// True for proc, sysfs, cgroup and other filesystems without backing storage
+    pub fn is_pseudo(&self) -> bool {
+        PSEUDO_FS.contains(&self.fs_type.as_str())
+    }
+
+    /// True for tmpfs and other memory backed filesystems
+    pub fn is_tmpfs(&self) -> bool {
+        TMP_FS.contains(&self.fs_type.as_str())
+    }
+
+    /// Mount source, a device path for block devices or a name such as "tmpfs"
+    pub fn get_dev(&self) -> &Path {
+        &self.dev
+    }
+
+    pub fn get_mnt(&self) -> &Path {
+        &self.mnt
+    }
+
+    pub fn get_fs_type(&self) -> &str {
+        &self.fs_type
+    }
+
+    pub fn get_options(&self) -> &MountOptions {
+        &self.options
+    }
+
+    /// fstab dump field, whether dump(8) should back up the filesystem
+    pub fn get_dump(&self) -> u32 {
+        self.dump
+    }
+
+    /// fstab pass field, the fsck order at boot
+    pub fn get_pass(&self) -> u32 {
+        self.pass
+    }
+
     pub fn is_read_only(&self) -> bool {
-        let split: Vec<&str> = self.attrs.split(',').collect();
-        split[0] == "ro"
+        self.options.is_read_only()
     }
 
-    pub fn get_uuid(&self) -> Result<String, String> {
-        if self.dev.contains("dev") & !self.dev.contains("tmpfs") & !self.dev.contains("pts")
-            & !self.dev.contains("cgroup")
-        {
-            for entry in Path::read_dir(Path::new("/dev/disk/by-uuid/")).unwrap() {
-                let e = entry.unwrap().path();
-                let l = Path::read_link(&e).unwrap();
-                let dev = Path::new(&self.dev);
-                let dev = dev.strip_prefix("/dev");
-                if l.strip_prefix("../../").unwrap().to_str().unwrap()
-                    == dev.unwrap().to_str().unwrap()
-                {
-                    return Ok(e.strip_prefix("/dev/disk/by-uuid/")
-                        .unwrap()
-                        .to_str()
-                        .unwrap()
-                        .to_string());
+    /// Writable mounts that allow executing binaries, the ones a security audit cares about
+    pub fn is_writable_exec(&self) -> bool {
+        !self.options.is_read_only() && !self.options.is_noexec()
+    }
+
+    /// major:minor of the device the mounted filesystem lives on, as used in
+    /// /proc/diskstats and /sys/dev/block. It comes from mountinfo rather than a stat of
+    /// the mount point, which can hang on an unresponsive NFS server
+    pub fn device_number(&self) -> Result<(u32, u32), io::Error> {
+        let info = MountInfo::parse(&fs::read(MOUNTINFO)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.device_number_in(&info)
+    }
+
+    /// device_number against mountinfo that was already read, so joining every mount to
+    /// its device reads the file once. Filesystems without a block device, e.g. tmpfs,
+    /// overlay and NFS, have an anonymous 0:N number that matches no disk. btrfs uses one
+    /// too, so when the source is a device node its number is used instead
+    pub fn device_number_in(&self, info: &MountInfo) -> Result<(u32, u32), io::Error> {
+        let entry = match info.for_mount_point(&self.mnt) {
+            Some(e) => e,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{} is not in mountinfo", self.mnt.display()),
+                ))
+            }
+        };
+        if entry.get_major() == 0 && self.dev.starts_with("/dev") {
+            if let Ok(meta) = fs::metadata(&self.dev) {
+                if meta.file_type().is_block_device() {
+                    return Ok(split_dev(meta.rdev()));
                 }
             }
-            return Err("Not Found".to_string());
-        } else {
-            return Err("Not Found".to_string());
         }
+        Ok((entry.get_major(), entry.get_minor()))
+    }
+
+    /// UUID, labels and canonical device node of the block device behind the mount
+    pub fn identity(&self) -> Result<BlockIdentity, BlockIdentityError> {
+        match self.dev.to_str() {
+            Some(d) => BlockIdentity::resolve(d),
+            None => Err(BlockIdentityError::NotFound(
+                self.dev.to_string_lossy().into_owned(),
+            )),
+        }
+    }
+
+    pub fn get_uuid(&self) -> Result<String, String> {
+        self.identity()
+            .ok()
+            .and_then(|i| i.get_uuid().map(|u| u.to_string()))
+            .ok_or_else(|| "Not Found".to_string())
     }
 }
 
 impl fmt::Display for Mount {
     fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmtr, "Device: {} Mount: {}", self.dev, self.mnt)
+        write!(
+            fmtr,
+            "Device: {} Mount: {}",
+            self.dev.display(),
+            self.mnt.display()
+        )
     }
 }
 
 impl PartialEq for Mount {
     fn eq(&self, other: &Mount) -> bool {
         (self.dev == other.dev) & (self.mnt == other.mnt) & (self.fs_type == other.fs_type)
-            & (self.attrs == other.attrs) & (self.dummy == other.dummy)
+            & (self.options == other.options) & (self.dump == other.dump)
+            & (self.pass == other.pass)
     }
 }
 
@@ -70,33 +465,53 @@ impl Mounts {
         self.mounts
     }
 
+    /// Mounts that hold real data, skipping pseudo filesystems and optionally tmpfs
+    pub fn real_mounts(self, include_tmpfs: bool) -> Vec<Mount> {
+        self.mounts
+            .into_iter()
+            .filter(|m| !m.is_pseudo() && (include_tmpfs || !m.is_tmpfs()))
+            .collect()
+    }
+
     pub fn new() -> Mounts {
         let s = Mounts::fetch();
-        Mounts::parse(s)
+        Mounts::parse(&s)
     }
 
-    fn fetch() -> String {
-        let mut s = String::new();
+    /// Mount paths may not be UTF-8 so the table is read as bytes
+    fn fetch() -> Vec<u8> {
+        let mut s = vec![];
         match fs::File::open(MOUNTS) {
             Ok(f) => {
                 let mut buf = BufReader::new(f);
-                buf.read_to_string(&mut s).unwrap();
+                buf.read_to_end(&mut s).unwrap();
                 s
             }
             Err(e) => panic!("{}", e),
         }
     }
 
-    fn parse(s: String) -> Mounts {
+    pub(crate) fn parse(s: &[u8]) -> Mounts {
         let mut mounts = Mounts { mounts: vec![] };
-        for line in s.lines() {
-            let line_split: Vec<&str> = line.split_whitespace().collect();
+        for line in s.split(|b| *b == b'\n') {
+            // fields are separated by single spaces, the escapes mean they never contain one
+            let line_split: Vec<&[u8]> = line.split(|b| *b == b' ' || *b == b'\t')
+                .filter(|f| !f.is_empty())
+                .collect();
+            if line_split.len() < 4 {
+                continue;
+            }
+            let text = |i: usize| {
+                let field = line_split.get(i).map_or(&b""[..], |f| *f);
+                String::from_utf8_lossy(field).into_owned()
+            };
             let m = Mount {
-                dev: line_split[0].to_string(),
-                mnt: line_split[1].to_string(),
-                fs_type: line_split[2].to_string(),
-                attrs: line_split[3].to_string(),
-                dummy: vec![line_split[4].to_string(), line_split[5].to_string()],
+                dev: unescape(line_split[0]),
+                mnt: unescape(line_split[1]),
+                fs_type: text(2),
+                options: MountOptions::parse_bytes(line_split.get(3).map_or(&b""[..], |f| *f)),
+                dump: text(4).parse::<u32>().unwrap_or(0),
+                pass: text(5).parse::<u32>().unwrap_or(0),
             };
             mounts.mounts.push(m)
         }
@@ -110,7 +525,7 @@ fn test_get_uuid() {
 /dev/nvme0n1p1 /boot/efi vfat rw,relatime,fmask=0077,dmask=0077,codepage=437,iocharset=ascii,shortname=winnt,errors=remount-ro 0 0
 selinuxfs /sys/fs/selinux selinuxfs rw,relatime 0 0
 ";
-    let p = Mounts::parse(uuid_test.to_string());
+    let p = Mounts::parse(uuid_test.as_bytes());
     let m1 = &p.mounts[0];
     let m2 = &p.mounts[1];
     let m3 = &p.mounts[2];
@@ -122,10 +537,105 @@ selinuxfs /sys/fs/selinux selinuxfs rw,relatime 0 0
     assert_eq!(m3.get_uuid(), Err("Not Found".to_string()));
 }
 
+#[test]
+fn test_mounts_real() {
+    let mounts_test = "sysfs /sys sysfs rw,seclabel,nosuid,nodev,noexec,relatime 0 0
+/dev/nvme0n1p2 /boot ext4 rw,seclabel,relatime,data=ordered 0 0
+tmpfs /tmp tmpfs rw,seclabel,nosuid,nodev 0 0
+cgroup2 /sys/fs/cgroup cgroup2 rw,nosuid,nodev,noexec,relatime 0 0";
+    let p = Mounts::parse(mounts_test.as_bytes());
+    assert_eq!(p.real_mounts(true).len(), 2);
+    let p = Mounts::parse(mounts_test.as_bytes());
+    let real = p.real_mounts(false);
+    assert_eq!(real.len(), 1);
+    assert_eq!(real[0].mnt, PathBuf::from("/boot"))
+}
+
+#[test]
+fn test_mount_usage() {
+    let u = MountUsage {
+        block_size: 4096,
+        total: 1000 * 4096,
+        free: 300 * 4096,
+        available: 250 * 4096,
+        inodes: 1000,
+        inodes_free: 999,
+    };
+    assert_eq!(u.used(), 700 * 4096);
+    assert_eq!(u.used_percent(), 74);
+    assert_eq!(u.inodes_used_percent(), 1);
+    assert!(MountUsage::statvfs(Path::new("/")).unwrap().get_total() > 0)
+}
+
+#[test]
+fn test_mount_options() {
+    let mounts_test = "/dev/sda1 /data ext4 rw,nosuid,noatime,errors=remount-ro,data=ordered 1 2
+tmpfs /dev/shm tmpfs rw,nosuid,nodev,noexec,size=65536k,mode=1777 0 0";
+    let p = Mounts::parse(mounts_test.as_bytes());
+    let data = &p.mounts[0];
+    assert_eq!(data.get_dump(), 1);
+    assert_eq!(data.get_pass(), 2);
+    assert!(data.get_options().is_nosuid());
+    assert!(!data.get_options().is_nodev());
+    assert_eq!(data.get_options().atime(), Atime::Noatime);
+    assert_eq!(data.get_options().get("errors"), Some("remount-ro"));
+    assert!(data.is_writable_exec());
+    let shm = &p.mounts[1];
+    assert_eq!(shm.get_options().get("size"), Some("65536k"));
+    assert_eq!(shm.get_options().atime(), Atime::Strictatime);
+    assert!(!shm.is_writable_exec())
+}
+
+#[test]
+fn test_mounts_escaped_paths() {
+    let mounts_test = b"//nas/share\\040one /mnt/My\\040Share cifs rw,relatime 0 0
+/dev/sdb1 /media/usb\\011tab\\134back\\012nl vfat rw 0 0
+/dev/sdc1 /media/caf\xe9 vfat rw 0 0
+";
+    let p = Mounts::parse(mounts_test);
+    assert_eq!(p.mounts[0].get_dev(), Path::new("//nas/share one"));
+    assert_eq!(p.mounts[0].get_mnt(), Path::new("/mnt/My Share"));
+    assert_eq!(p.mounts[0].get_fs_type(), "cifs");
+    assert_eq!(p.mounts[1].get_mnt(), Path::new("/media/usb\ttab\\back\nnl"));
+    assert_eq!(
+        p.mounts[2].get_mnt().as_os_str().as_bytes(),
+        &b"/media/caf\xe9"[..]
+    );
+    assert_eq!(unescape(b"trailing\\04"), PathBuf::from("trailing\\04"))
+}
+
+#[test]
+fn test_split_dev() {
+    assert_eq!(split_dev(0x10302), (259, 2));
+    assert_eq!(split_dev(0xfd00), (253, 0))
+}
+
+#[test]
+fn test_device_number_in() {
+    let info = MountInfo::parse(
+        b"22 1 253:0 / / rw,relatime shared:1 - ext4 /dev/mapper/vg-root rw
+41 22 0:38 / /srv/nfs rw,relatime shared:23 - nfs4 nas:/export rw,vers=4.2
+42 22 0:39 / /tmp rw,nosuid shared:24 - tmpfs tmpfs rw
+43 42 0:40 / /tmp rw,nosuid shared:25 - tmpfs tmpfs rw,size=1024k
+",
+    ).unwrap();
+    let mounts = Mounts::parse(
+        b"/dev/mapper/vg-root / ext4 rw,relatime 0 0
+nas:/export /srv/nfs nfs4 rw,relatime,vers=4.2 0 0
+tmpfs /tmp tmpfs rw,nosuid 0 0
+/dev/sdz1 /mnt ext4 rw 0 0
+",
+    ).get_mounts();
+    assert_eq!(mounts[0].device_number_in(&info).unwrap(), (253, 0));
+    assert_eq!(mounts[1].device_number_in(&info).unwrap(), (0, 38));
+    assert_eq!(mounts[2].device_number_in(&info).unwrap(), (0, 40));
+    assert!(mounts[3].device_number_in(&info).is_err())
+}
+
 #[test]
 fn test_mounts_read_only_true() {
     let mounts_test = "sysfs /sys sysfs ro,seclabel,nosuid,nodev,noexec,relatime 0 0";
-    let p = Mounts::parse(mounts_test.to_string());
+    let p = Mounts::parse(mounts_test.as_bytes());
     for mount in p.mounts {
         assert_eq!(true, mount.is_read_only())
     }
@@ -136,7 +646,7 @@ fn test_mounts_read_only_false() {
     let mounts_test = "sysfs /sys sysfs rw,seclabel,nosuid,nodev,noexec,relatime 0 0
 proc /proc proc rw,nosuid,nodev,noexec,relatime 0 0
 devtmpfs /dev devtmpfs rw,seclabel,nosuid,size=8073728k,nr_inodes=2018432,mode=755 0 0";
-    let p = Mounts::parse(mounts_test.to_string());
+    let p = Mounts::parse(mounts_test.as_bytes());
     for mount in p.mounts {
         assert_eq!(false, mount.is_read_only())
     }
@@ -147,29 +657,32 @@ fn test_mounts_parse() {
     let mounts_test = "sysfs /sys sysfs rw,seclabel,nosuid,nodev,noexec,relatime 0 0
 proc /proc proc rw,nosuid,nodev,noexec,relatime 0 0
 devtmpfs /dev devtmpfs rw,seclabel,nosuid,size=8073728k,nr_inodes=2018432,mode=755 0 0";
-    let p = Mounts::parse(mounts_test.to_string());
+    let p = Mounts::parse(mounts_test.as_bytes());
     let test = Mounts {
         mounts: vec![
             Mount {
-                dev: "sysfs".to_string(),
-                mnt: "/sys".to_string(),
+                dev: PathBuf::from("sysfs"),
+                mnt: PathBuf::from("/sys"),
                 fs_type: "sysfs".to_string(),
-                attrs: "rw,seclabel,nosuid,nodev,noexec,relatime".to_string(),
-                dummy: vec!["0".to_string(), "0".to_string()],
+                options: MountOptions::parse("rw,seclabel,nosuid,nodev,noexec,relatime"),
+                dump: 0,
+                pass: 0,
             },
             Mount {
-                dev: "proc".to_string(),
-                mnt: "/proc".to_string(),
+                dev: PathBuf::from("proc"),
+                mnt: PathBuf::from("/proc"),
                 fs_type: "proc".to_string(),
-                attrs: "rw,nosuid,nodev,noexec,relatime".to_string(),
-                dummy: vec!["0".to_string(), "0".to_string()],
+                options: MountOptions::parse("rw,nosuid,nodev,noexec,relatime"),
+                dump: 0,
+                pass: 0,
             },
             Mount {
-                dev: "devtmpfs".to_string(),
-                mnt: "/dev".to_string(),
+                dev: PathBuf::from("devtmpfs"),
+                mnt: PathBuf::from("/dev"),
                 fs_type: "devtmpfs".to_string(),
-                attrs: "rw,seclabel,nosuid,size=8073728k,nr_inodes=2018432,mode=755".to_string(),
-                dummy: vec!["0".to_string(), "0".to_string()],
+                options: MountOptions::parse("rw,seclabel,nosuid,size=8073728k,nr_inodes=2018432,mode=755"),
+                dump: 0,
+                pass: 0,
             },
         ],
     };
@@ -0,0 +1,933 @@
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use mounts::{unescape, Mount};
+
+pub static MOUNTSTATS: &str = "/proc/self/mountstats";
+pub static CIFS_STATS: &str = "/proc/fs/cifs/Stats";
+
+/// Per operation RPC counters from the "per-op statistics" section, times are the
+/// cumulative milliseconds across all ops
+#[derive(Debug, Clone)]
+pub struct NfsOp {
+    name: String,
+    ops: u64,
+    transmissions: u64,
+    timeouts: u64,
+    bytes_sent: u64,
+    bytes_recv: u64,
+    queue_ms: u64,
+    rtt_ms: u64,
+    execute_ms: u64,
+    errors: u64,
+}
+
+impl PartialEq for NfsOp {
+    fn eq(&self, other: &NfsOp) -> bool {
+        (self.name == other.name) & (self.ops == other.ops)
+            & (self.transmissions == other.transmissions) & (self.timeouts == other.timeouts)
+            & (self.bytes_sent == other.bytes_sent) & (self.bytes_recv == other.bytes_recv)
+            & (self.queue_ms == other.queue_ms) & (self.rtt_ms == other.rtt_ms)
+            & (self.execute_ms == other.execute_ms) & (self.errors == other.errors)
+    }
+}
+
+impl fmt::Display for NfsOp {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "{}: Ops: {} Retrans: {} RTT: {} Exec: {}",
+            self.name,
+            self.ops,
+            self.retransmissions(),
+            self.rtt_ms,
+            self.execute_ms
+        )
+    }
+}
+
+impl NfsOp {
+    /// RPC procedure, e.g. READ, GETATTR
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_ops(&self) -> u64 {
+        self.ops
+    }
+
+    pub fn get_transmissions(&self) -> u64 {
+        self.transmissions
+    }
+
+    /// Major timeouts
+    pub fn get_timeouts(&self) -> u64 {
+        self.timeouts
+    }
+
+    pub fn get_bytes_sent(&self) -> u64 {
+        self.bytes_sent
+    }
+
+    pub fn get_bytes_recv(&self) -> u64 {
+        self.bytes_recv
+    }
+
+    /// Time spent queued before transmission
+    pub fn get_queue_ms(&self) -> u64 {
+        self.queue_ms
+    }
+
+    /// Round trip time on the wire
+    pub fn get_rtt_ms(&self) -> u64 {
+        self.rtt_ms
+    }
+
+    /// Time from the request being created until the reply is handled
+    pub fn get_execute_ms(&self) -> u64 {
+        self.execute_ms
+    }
+
+    /// Ops which completed with an error status, only reported by Linux 5.3 and later
+    pub fn get_errors(&self) -> u64 {
+        self.errors
+    }
+
+    /// Transmissions beyond the first for each op
+    pub fn retransmissions(&self) -> u64 {
+        self.transmissions.saturating_sub(self.ops)
+    }
+
+    /// Rates between an earlier sample and this one taken `secs` apart
+    pub fn rate(&self, prev: &NfsOp, secs: f64) -> NfsOpRate {
+        let ops = self.ops.saturating_sub(prev.ops);
+        let retrans = self.retransmissions().saturating_sub(prev.retransmissions());
+        let bytes = (self.bytes_sent + self.bytes_recv)
+            .saturating_sub(prev.bytes_sent + prev.bytes_recv);
+        let per_sec = |v: u64| if secs > 0.0 { v as f64 / secs } else { 0.0 };
+        let per_op = |ms: u64| if ops > 0 { ms as f64 / ops as f64 } else { 0.0 };
+        NfsOpRate {
+            name: self.name.clone(),
+            ops_per_sec: per_sec(ops),
+            retrans_per_sec: per_sec(retrans),
+            bytes_per_sec: per_sec(bytes),
+            avg_rtt_ms: per_op(self.rtt_ms.saturating_sub(prev.rtt_ms)),
+            avg_exec_ms: per_op(self.execute_ms.saturating_sub(prev.execute_ms)),
+        }
+    }
+
+    fn parse(line: &str) -> Option<NfsOp> {
+        let i = line.find(':')?;
+        let values: Vec<u64> = line[i + 1..]
+            .split_whitespace()
+            .map(|v| v.parse::<u64>().unwrap_or(0))
+            .collect();
+        if values.len() < 8 {
+            return None;
+        }
+        Some(NfsOp {
+            name: line[..i].trim().to_string(),
+            ops: values[0],
+            transmissions: values[1],
+            timeouts: values[2],
+            bytes_sent: values[3],
+            bytes_recv: values[4],
+            queue_ms: values[5],
+            rtt_ms: values[6],
+            execute_ms: values[7],
+            errors: values.get(8).cloned().unwrap_or(0),
+        })
+    }
+}
+
+/// Per second rates and per op latency for one RPC procedure
+#[derive(Debug)]
+pub struct NfsOpRate {
+    name: String,
+    ops_per_sec: f64,
+    retrans_per_sec: f64,
+    bytes_per_sec: f64,
+    avg_rtt_ms: f64,
+    avg_exec_ms: f64,
+}
+
+impl fmt::Display for NfsOpRate {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "{}: {:.1} ops/s {:.1} retrans/s {:.1} B/s RTT: {:.2}ms Exec: {:.2}ms",
+            self.name,
+            self.ops_per_sec,
+            self.retrans_per_sec,
+            self.bytes_per_sec,
+            self.avg_rtt_ms,
+            self.avg_exec_ms
+        )
+    }
+}
+
+impl NfsOpRate {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_ops_per_sec(&self) -> f64 {
+        self.ops_per_sec
+    }
+
+    pub fn get_retrans_per_sec(&self) -> f64 {
+        self.retrans_per_sec
+    }
+
+    /// Bytes sent and received per second, including RPC headers
+    pub fn get_bytes_per_sec(&self) -> f64 {
+        self.bytes_per_sec
+    }
+
+    pub fn get_avg_rtt_ms(&self) -> f64 {
+        self.avg_rtt_ms
+    }
+
+    pub fn get_avg_exec_ms(&self) -> f64 {
+        self.avg_exec_ms
+    }
+}
+
+/// Transport counters from the xprt line. udp has no connection so connect_count,
+/// connect_time and idle_time are 0 for it
+#[derive(Debug, Clone)]
+pub struct NfsXprt {
+    protocol: String,
+    port: u64,
+    bind_count: u64,
+    connect_count: u64,
+    connect_time: u64,
+    idle_time: u64,
+    sends: u64,
+    recvs: u64,
+    bad_xids: u64,
+    req_u: u64,
+    backlog_u: u64,
+}
+
+impl PartialEq for NfsXprt {
+    fn eq(&self, other: &NfsXprt) -> bool {
+        (self.protocol == other.protocol) & (self.port == other.port)
+            & (self.bind_count == other.bind_count)
+            & (self.connect_count == other.connect_count)
+            & (self.connect_time == other.connect_time) & (self.idle_time == other.idle_time)
+            & (self.sends == other.sends) & (self.recvs == other.recvs)
+            & (self.bad_xids == other.bad_xids) & (self.req_u == other.req_u)
+            & (self.backlog_u == other.backlog_u)
+    }
+}
+
+impl NfsXprt {
+    /// tcp, udp or rdma
+    pub fn get_protocol(&self) -> &str {
+        &self.protocol
+    }
+
+    /// Local port
+    pub fn get_port(&self) -> u64 {
+        self.port
+    }
+
+    pub fn get_bind_count(&self) -> u64 {
+        self.bind_count
+    }
+
+    /// Number of times the client has had to (re)connect to the server
+    pub fn get_connect_count(&self) -> u64 {
+        self.connect_count
+    }
+
+    /// Jiffies spent waiting for connections
+    pub fn get_connect_time(&self) -> u64 {
+        self.connect_time
+    }
+
+    /// Seconds since the transport was last used
+    pub fn get_idle_time(&self) -> u64 {
+        self.idle_time
+    }
+
+    pub fn get_sends(&self) -> u64 {
+        self.sends
+    }
+
+    pub fn get_recvs(&self) -> u64 {
+        self.recvs
+    }
+
+    /// Replies which matched no outstanding request
+    pub fn get_bad_xids(&self) -> u64 {
+        self.bad_xids
+    }
+
+    /// Cumulative request slot utilisation, divide by sends for the average
+    pub fn get_req_u(&self) -> u64 {
+        self.req_u
+    }
+
+    /// Cumulative backlog queue length, divide by sends for the average
+    pub fn get_backlog_u(&self) -> u64 {
+        self.backlog_u
+    }
+
+    fn parse(s: &str) -> Option<NfsXprt> {
+        let mut split = s.split_whitespace();
+        let protocol = split.next()?.to_string();
+        let v: Vec<u64> = split.map(|v| v.parse::<u64>().unwrap_or(0)).collect();
+        let get = |i: usize| v.get(i).cloned().unwrap_or(0);
+        let xprt = if protocol == "udp" {
+            NfsXprt {
+                protocol,
+                port: get(0),
+                bind_count: get(1),
+                connect_count: 0,
+                connect_time: 0,
+                idle_time: 0,
+                sends: get(2),
+                recvs: get(3),
+                bad_xids: get(4),
+                req_u: get(5),
+                backlog_u: get(6),
+            }
+        } else {
+            NfsXprt {
+                protocol,
+                port: get(0),
+                bind_count: get(1),
+                connect_count: get(2),
+                connect_time: get(3),
+                idle_time: get(4),
+                sends: get(5),
+                recvs: get(6),
+                bad_xids: get(7),
+                req_u: get(8),
+                backlog_u: get(9),
+            }
+        };
+        Some(xprt)
+    }
+}
+
+/// Statistics for one NFS mount. CIFS mounts are listed in mountstats without any
+/// statistics so only nfs and nfs4 mounts are kept
+#[derive(Debug, Clone)]
+pub struct NfsStats {
+    device: PathBuf,
+    mnt: PathBuf,
+    fs_type: String,
+    age: u64,
+    bytes: Vec<u64>,
+    xprt: Option<NfsXprt>,
+    ops: Vec<NfsOp>,
+}
+
+impl PartialEq for NfsStats {
+    fn eq(&self, other: &NfsStats) -> bool {
+        (self.device == other.device) & (self.mnt == other.mnt)
+            & (self.fs_type == other.fs_type) & (self.age == other.age)
+            & (self.bytes == other.bytes) & (self.xprt == other.xprt)
+            & (self.ops == other.ops)
+    }
+}
+
+impl fmt::Display for NfsStats {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "Device: {} Mount: {} Age: {}",
+            self.device.display(),
+            self.mnt.display(),
+            self.age
+        )
+    }
+}
+
+impl NfsStats {
+    /// server:/export
+    pub fn get_device(&self) -> &Path {
+        &self.device
+    }
+
+    pub fn get_mnt(&self) -> &Path {
+        &self.mnt
+    }
+
+    pub fn get_fs_type(&self) -> &str {
+        &self.fs_type
+    }
+
+    /// Seconds since the mount was made
+    pub fn get_age(&self) -> u64 {
+        self.age
+    }
+
+    /// Bytes read and written by applications through the page cache
+    pub fn get_normal_read(&self) -> u64 {
+        self.bytes_field(0)
+    }
+
+    pub fn get_normal_write(&self) -> u64 {
+        self.bytes_field(1)
+    }
+
+    /// Bytes read and written with O_DIRECT
+    pub fn get_direct_read(&self) -> u64 {
+        self.bytes_field(2)
+    }
+
+    pub fn get_direct_write(&self) -> u64 {
+        self.bytes_field(3)
+    }
+
+    /// Bytes actually read from and written to the server
+    pub fn get_server_read(&self) -> u64 {
+        self.bytes_field(4)
+    }
+
+    pub fn get_server_write(&self) -> u64 {
+        self.bytes_field(5)
+    }
+
+    pub fn get_xprt(&self) -> Option<&NfsXprt> {
+        self.xprt.as_ref()
+    }
+
+    pub fn get_ops(&self) -> &Vec<NfsOp> {
+        &self.ops
+    }
+
+    pub fn op(&self, name: &str) -> Option<&NfsOp> {
+        self.ops.iter().find(|o| o.name == name)
+    }
+
+    /// Retransmissions across every op, anything above zero means the server or network
+    /// is dropping requests
+    pub fn retransmissions(&self) -> u64 {
+        self.ops.iter().map(|o| o.retransmissions()).sum()
+    }
+
+    /// Rates for every op present in both samples
+    pub fn rates(&self, prev: &NfsStats, secs: f64) -> Vec<NfsOpRate> {
+        let mut rates = vec![];
+        for op in &self.ops {
+            if let Some(p) = prev.op(&op.name) {
+                rates.push(op.rate(p, secs))
+            }
+        }
+        rates
+    }
+
+    fn bytes_field(&self, i: usize) -> u64 {
+        self.bytes.get(i).cloned().unwrap_or(0)
+    }
+}
+
+#[derive(Debug)]
+pub struct MountStats {
+    mounts: Vec<NfsStats>,
+}
+
+impl MountStats {
+    pub fn get_mounts(self) -> Vec<NfsStats> {
+        self.mounts
+    }
+
+    pub fn new() -> MountStats {
+        let data = MountStats::fetch();
+        MountStats::parse(&data)
+    }
+
+    pub fn get(&self, mnt: &Path) -> Option<&NfsStats> {
+        self.mounts.iter().rev().find(|m| m.mnt == mnt)
+    }
+
+    /// Statistics for a mount from Mounts
+    pub fn for_mount(&self, mount: &Mount) -> Option<&NfsStats> {
+        self.mounts
+            .iter()
+            .rev()
+            .find(|m| m.mnt == mount.get_mnt() && m.device == mount.get_dev())
+    }
+
+    /// Each NFS mount paired with its statistics
+    pub fn join<'a>(&'a self, mounts: &'a [Mount]) -> Vec<(&'a Mount, &'a NfsStats)> {
+        mounts
+            .iter()
+            .filter_map(|m| self.for_mount(m).map(|s| (m, s)))
+            .collect()
+    }
+
+    fn fetch() -> Vec<u8> {
+        let mut data = vec![];
+        let mut f = File::open(MOUNTSTATS).expect("Unable to open file");
+        f.read_to_end(&mut data).expect("Unable to read string");
+        data
+    }
+
+    /// Every line after "per-op statistics" up to the next device is an op
+    fn parse(data: &[u8]) -> MountStats {
+        let mut mounts = vec![];
+        let mut current: Option<NfsStats> = None;
+        let mut in_ops = false;
+        for line in data.split(|b| *b == b'\n') {
+            if line.starts_with(b"device ") {
+                mounts.extend(current.take());
+                current = MountStats::parse_device(line);
+                in_ops = false;
+                continue;
+            }
+            let stats = match current {
+                Some(ref mut s) => s,
+                None => continue,
+            };
+            let line = String::from_utf8_lossy(line);
+            let line = line.trim();
+            if in_ops {
+                stats.ops.extend(NfsOp::parse(line));
+                continue;
+            }
+            if line == "per-op statistics" {
+                in_ops = true;
+                continue;
+            }
+            let (key, value) = match line.find(':') {
+                Some(i) => (&line[..i], line[i + 1..].trim()),
+                None => continue,
+            };
+            let numbers = || {
+                value
+                    .split_whitespace()
+                    .map(|v| v.parse::<u64>().unwrap_or(0))
+                    .collect::<Vec<u64>>()
+            };
+            match key {
+                "age" => stats.age = value.parse::<u64>().unwrap_or(0),
+                "bytes" => stats.bytes = numbers(),
+                "xprt" => stats.xprt = NfsXprt::parse(value),
+                _ => (),
+            }
+        }
+        mounts.extend(current.take());
+        MountStats { mounts }
+    }
+
+    /// device <source> mounted on <mount point> with fstype <type> [statvers=<v>]
+    fn parse_device(line: &[u8]) -> Option<NfsStats> {
+        let fields: Vec<&[u8]> = line.split(|b| *b == b' ').filter(|f| !f.is_empty()).collect();
+        if fields.len() < 8 {
+            return None;
+        }
+        let fs_type = String::from_utf8_lossy(fields[7]).into_owned();
+        if !fs_type.starts_with("nfs") {
+            return None;
+        }
+        Some(NfsStats {
+            device: unescape(fields[1]),
+            mnt: unescape(fields[4]),
+            fs_type,
+            age: 0,
+            bytes: vec![],
+            xprt: None,
+            ops: vec![],
+        })
+    }
+}
+
+/// Requests sent for one SMB command on a share, e.g. Reads or Creates
+#[derive(Debug, Clone)]
+pub struct CifsOp {
+    name: String,
+    total: u64,
+    failed: u64,
+}
+
+impl PartialEq for CifsOp {
+    fn eq(&self, other: &CifsOp) -> bool {
+        (self.name == other.name) & (self.total == other.total) & (self.failed == other.failed)
+    }
+}
+
+impl fmt::Display for CifsOp {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmtr, "{}: {} total {} failed", self.name, self.total, self.failed)
+    }
+}
+
+impl CifsOp {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn get_failed(&self) -> u64 {
+        self.failed
+    }
+
+    /// Rates between an earlier sample and this one taken `secs` apart
+    pub fn rate(&self, prev: &CifsOp, secs: f64) -> CifsOpRate {
+        let per_sec = |v: u64| if secs > 0.0 { v as f64 / secs } else { 0.0 };
+        CifsOpRate {
+            name: self.name.clone(),
+            ops_per_sec: per_sec(self.total.saturating_sub(prev.total)),
+            failed_per_sec: per_sec(self.failed.saturating_sub(prev.failed)),
+        }
+    }
+
+    /// "Reads: 10 total 0 failed", OplockBreaks says sent instead of total
+    fn parse(line: &str) -> Option<CifsOp> {
+        let i = line.find(':')?;
+        let values: Vec<&str> = line[i + 1..].split_whitespace().collect();
+        let num = |j: usize| values.get(j).and_then(|v| v.parse::<u64>().ok());
+        if values.get(3) != Some(&"failed") {
+            return None;
+        }
+        Some(CifsOp {
+            name: line[..i].trim().to_string(),
+            total: num(0)?,
+            failed: num(2)?,
+        })
+    }
+}
+
+/// Per second rates for one SMB command
+#[derive(Debug)]
+pub struct CifsOpRate {
+    name: String,
+    ops_per_sec: f64,
+    failed_per_sec: f64,
+}
+
+impl fmt::Display for CifsOpRate {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "{}: {:.1} ops/s {:.1} failed/s",
+            self.name, self.ops_per_sec, self.failed_per_sec
+        )
+    }
+}
+
+impl CifsOpRate {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_ops_per_sec(&self) -> f64 {
+        self.ops_per_sec
+    }
+
+    pub fn get_failed_per_sec(&self) -> f64 {
+        self.failed_per_sec
+    }
+}
+
+/// Counters for one share from /proc/fs/cifs/Stats. Commands are listed in the SMB2 and
+/// later layout, SMB1 shares have no failure counts so only the totals are kept
+#[derive(Debug, Clone)]
+pub struct CifsShare {
+    share: PathBuf,
+    disconnected: bool,
+    smbs: u64,
+    bytes_read: u64,
+    bytes_written: u64,
+    ops: Vec<CifsOp>,
+}
+
+impl PartialEq for CifsShare {
+    fn eq(&self, other: &CifsShare) -> bool {
+        (self.share == other.share) & (self.disconnected == other.disconnected)
+            & (self.smbs == other.smbs) & (self.bytes_read == other.bytes_read)
+            & (self.bytes_written == other.bytes_written) & (self.ops == other.ops)
+    }
+}
+
+impl fmt::Display for CifsShare {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmtr, "Share: {} SMBs: {}", self.share.display(), self.smbs)?;
+        if self.disconnected {
+            write!(fmtr, " DISCONNECTED")?;
+        }
+        Ok(())
+    }
+}
+
+impl CifsShare {
+    /// //server/share, written with forward slashes as in /proc/mounts
+    pub fn get_share(&self) -> &Path {
+        &self.share
+    }
+
+    /// The connection to the server was lost and is waiting to reconnect
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnected
+    }
+
+    /// Requests sent on the share
+    pub fn get_smbs(&self) -> u64 {
+        self.smbs
+    }
+
+    pub fn get_bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    pub fn get_bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    pub fn get_ops(&self) -> &Vec<CifsOp> {
+        &self.ops
+    }
+
+    pub fn op(&self, name: &str) -> Option<&CifsOp> {
+        self.ops.iter().find(|o| o.name == name)
+    }
+
+    /// Failed requests across every command
+    pub fn failed(&self) -> u64 {
+        self.ops.iter().map(|o| o.failed).sum()
+    }
+
+    /// Rates for every command present in both samples
+    pub fn rates(&self, prev: &CifsShare, secs: f64) -> Vec<CifsOpRate> {
+        let mut rates = vec![];
+        for op in &self.ops {
+            if let Some(p) = prev.op(&op.name) {
+                rates.push(op.rate(p, secs))
+            }
+        }
+        rates
+    }
+
+    /// Bytes per second read and written between an earlier sample and this one
+    pub fn bytes_rate(&self, prev: &CifsShare, secs: f64) -> (f64, f64) {
+        if secs <= 0.0 {
+            return (0.0, 0.0);
+        }
+        (
+            self.bytes_read.saturating_sub(prev.bytes_read) as f64 / secs,
+            self.bytes_written.saturating_sub(prev.bytes_written) as f64 / secs,
+        )
+    }
+
+    /// "1) \\server\share" optionally followed by DISCONNECTED
+    fn parse_header(line: &str) -> Option<CifsShare> {
+        let i = line.find(')')?;
+        line[..i].parse::<u32>().ok()?;
+        let rest = line[i + 1..].trim();
+        let disconnected = rest.ends_with("DISCONNECTED");
+        let share = rest.trim_end_matches("DISCONNECTED").trim().replace('\\', "/");
+        Some(CifsShare {
+            share: PathBuf::from(share),
+            disconnected,
+            smbs: 0,
+            bytes_read: 0,
+            bytes_written: 0,
+            ops: vec![],
+        })
+    }
+}
+
+/// SMB client statistics for every mounted share, needs the cifs module to be loaded
+#[derive(Debug)]
+pub struct CifsStats {
+    shares: Vec<CifsShare>,
+}
+
+impl CifsStats {
+    pub fn get_shares(self) -> Vec<CifsShare> {
+        self.shares
+    }
+
+    pub fn new() -> Result<CifsStats, io::Error> {
+        Ok(CifsStats::parse(&fs::read_to_string(CIFS_STATS)?))
+    }
+
+    pub fn get(&self, share: &Path) -> Option<&CifsShare> {
+        self.shares.iter().find(|s| s.share == share)
+    }
+
+    /// Statistics for a cifs or smb3 mount from Mounts
+    pub fn for_mount(&self, mount: &Mount) -> Option<&CifsShare> {
+        match mount.get_fs_type() {
+            "cifs" | "smb3" => self.get(mount.get_dev()),
+            _ => None,
+        }
+    }
+
+    /// Each SMB mount paired with its statistics
+    pub fn join<'a>(&'a self, mounts: &'a [Mount]) -> Vec<(&'a Mount, &'a CifsShare)> {
+        mounts
+            .iter()
+            .filter_map(|m| self.for_mount(m).map(|s| (m, s)))
+            .collect()
+    }
+
+    /// Session totals come first and are skipped, each share starts with a numbered
+    /// header line
+    fn parse(s: &str) -> CifsStats {
+        let mut shares = vec![];
+        let mut current: Option<CifsShare> = None;
+        for line in s.lines() {
+            let line = line.trim();
+            if let Some(share) = CifsShare::parse_header(line) {
+                shares.extend(current.take());
+                current = Some(share);
+                continue;
+            }
+            let share = match current {
+                Some(ref mut s) => s,
+                None => continue,
+            };
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let num = |i: usize| fields.get(i).and_then(|v| v.parse::<u64>().ok()).unwrap_or(0);
+            if line.starts_with("SMBs:") {
+                share.smbs = num(1);
+            } else if line.starts_with("Bytes read:") {
+                share.bytes_read = num(2);
+                share.bytes_written = num(5);
+            } else if let Some(op) = CifsOp::parse(line) {
+                share.ops.push(op);
+            }
+        }
+        shares.extend(current.take());
+        CifsStats { shares }
+    }
+}
+
+#[test]
+fn test_mountstats_parse() {
+    let data = b"device sysfs mounted on /sys with fstype sysfs
+device proc mounted on /proc with fstype proc
+device nas:/export/home mounted on /mnt/home\\040dir with fstype nfs4 statvers=1.1
+\topts:\trw,vers=4.2,rsize=1048576,wsize=1048576,namlen=255,acregmin=3
+\tage:\t86400
+\tcaps:\tcaps=0x3ffbf,wtmult=512,dtsize=32768,bsize=0,namlen=255
+\tsec:\tflavor=1,pseudoflavor=1
+\tevents:\t100 200 0 0 50 10 300 0 0 20 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0
+\tbytes:\t1000 2000 0 0 900 2000 3 5
+\tRPC iostats version: 1.1  p/v: 100003/4 (nfs)
+\txprt:\ttcp 875 1 2 0 10 5000 4990 0 12000 300 65 6000 0
+\tper-op statistics
+\t        NULL: 1 1 0 44 24 0 0 0 0
+\t        READ: 400 410 1 60000 4100000 20 3200 3400 0
+\t       WRITE: 100 100 0 2100000 14000 5 900 1000 2
+\t  GETDEVICELIST: 0 0 0 0 0 0 0 0 0
+
+device //fileserver/share mounted on /mnt/share with fstype cifs
+";
+    let stats = MountStats::parse(data);
+    let nfs = stats.get(Path::new("/mnt/home dir")).unwrap();
+    assert_eq!(nfs.get_device(), Path::new("nas:/export/home"));
+    assert_eq!(nfs.get_age(), 86400);
+    assert_eq!(nfs.get_server_read(), 900);
+    assert_eq!(nfs.get_ops().len(), 4);
+    assert_eq!(nfs.retransmissions(), 10);
+    let xprt = nfs.get_xprt().unwrap();
+    assert_eq!(xprt.get_connect_count(), 2);
+    assert_eq!(xprt.get_sends(), 5000);
+    assert_eq!(xprt.get_bad_xids(), 0);
+    assert_eq!(
+        nfs.op("WRITE").unwrap(),
+        &NfsOp {
+            name: "WRITE".to_string(),
+            ops: 100,
+            transmissions: 100,
+            timeouts: 0,
+            bytes_sent: 2100000,
+            bytes_recv: 14000,
+            queue_ms: 5,
+            rtt_ms: 900,
+            execute_ms: 1000,
+            errors: 2,
+        }
+    );
+
+    let mut later = nfs.clone();
+    later.ops[1].ops = 600;
+    later.ops[1].transmissions = 620;
+    later.ops[1].rtt_ms = 5200;
+    later.ops[1].execute_ms = 5800;
+    let rates = later.rates(nfs, 10.0);
+    assert_eq!(rates[1].get_ops_per_sec(), 20.0);
+    assert_eq!(rates[1].get_retrans_per_sec(), 1.0);
+    assert_eq!(rates[1].get_avg_rtt_ms(), 10.0);
+    assert_eq!(rates[1].get_avg_exec_ms(), 12.0);
+    assert_eq!(stats.get_mounts().len(), 1);
+}
+
+#[test]
+fn test_cifs_stats_parse() {
+    let data = "Resources in use
+CIFS Session: 1
+Share (unique mount targets): 2
+SMB Request/Response Buffer: 1 Pool size: 5
+SMB Small Req/Resp Buffer: 1 Pool size: 30
+Total Large 10 Small 62 Allocations
+Operations (MIDs): 0
+
+0 session 0 share reconnects
+Total vfs operations: 48 maximum at one time: 2
+
+Max requests in flight: 2
+1) \\\\fileserver\\IPC$
+SMBs: 2
+Bytes read: 0  Bytes written: 0
+Open files: 0 total (local), 0 open on server
+TreeConnects: 1 total 0 failed
+IOCTLs: 1 total 1 failed
+2) \\\\fileserver\\share\tDISCONNECTED 
+SMBs: 9000
+Bytes read: 1048576  Bytes written: 4096
+Open files: 2 total (local), 2 open on server
+TreeConnects: 1 total 0 failed
+Creates: 20 total 3 failed
+Reads: 256 total 0 failed
+Writes: 1 total 0 failed
+OplockBreaks: 0 sent 0 failed
+";
+    let stats = CifsStats::parse(data);
+    let share = stats.get(Path::new("//fileserver/share")).unwrap();
+    assert!(share.is_disconnected());
+    assert_eq!(share.get_smbs(), 9000);
+    assert_eq!(share.get_bytes_read(), 1048576);
+    assert_eq!(share.get_bytes_written(), 4096);
+    assert_eq!(share.get_ops().len(), 5);
+    assert_eq!(
+        share.op("Creates"),
+        Some(&CifsOp {
+            name: "Creates".to_string(),
+            total: 20,
+            failed: 3,
+        })
+    );
+    assert_eq!(share.failed(), 3);
+
+    let mounts = ::mounts::Mounts::parse(
+        b"//fileserver/share /mnt/share cifs rw,relatime,vers=3.1.1 0 0
+nas:/export /mnt/nfs nfs4 rw 0 0
+",
+    ).get_mounts();
+    let joined = stats.join(&mounts);
+    assert_eq!(joined.len(), 1);
+    assert_eq!(joined[0].0.get_mnt(), Path::new("/mnt/share"));
+
+    let mut later = share.clone();
+    later.ops[2].total = 1256;
+    later.bytes_read += 10485760;
+    let rates = later.rates(share, 10.0);
+    assert_eq!(rates[2].get_ops_per_sec(), 100.0);
+    assert_eq!(later.bytes_rate(share, 10.0), (1048576.0, 0.0));
+    assert_eq!(stats.get_shares().len(), 2)
+}
@@ -0,0 +1,224 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+use std::os::unix::io::AsRawFd;
+use libc;
+use mounts::{Mount, Mounts};
+
+/// The per process view of the mount table, the kernel raises POLLPRI and POLLERR on it
+/// whenever a mount in the namespace changes
+pub static SELF_MOUNTS: &str = "/proc/self/mounts";
+
+#[derive(Debug)]
+pub enum MountEvent {
+    Added(Mount),
+    Removed(Mount),
+    /// Same device at the same mount point with different options, e.g. a remount ro
+    Remounted { old: Mount, new: Mount },
+}
+
+impl PartialEq for MountEvent {
+    fn eq(&self, other: &MountEvent) -> bool {
+        match (self, other) {
+            (MountEvent::Added(a), MountEvent::Added(b)) => a == b,
+            (MountEvent::Removed(a), MountEvent::Removed(b)) => a == b,
+            (
+                MountEvent::Remounted { old: a, new: b },
+                MountEvent::Remounted { old: c, new: d },
+            ) => (a == c) & (b == d),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for MountEvent {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MountEvent::Added(ref m) => write!(fmtr, "Added: {}", m),
+            MountEvent::Removed(ref m) => write!(fmtr, "Removed: {}", m),
+            MountEvent::Remounted { ref old, ref new } => write!(
+                fmtr,
+                "Remounted: {} Options: {} -> {}",
+                new,
+                old.get_options(),
+                new.get_options()
+            ),
+        }
+    }
+}
+
+/// Watches the mount table and reports what changed between polls
+#[derive(Debug)]
+pub struct MountWatcher {
+    file: File,
+    mounts: Vec<Mount>,
+    pending: VecDeque<MountEvent>,
+}
+
+impl MountWatcher {
+    /// Open the mount table and take the initial snapshot events are reported against
+    pub fn new() -> Result<MountWatcher, io::Error> {
+        let mut file = File::open(SELF_MOUNTS)?;
+        let mounts = MountWatcher::read(&mut file)?;
+        Ok(MountWatcher {
+            file,
+            mounts,
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// The mount table as of the last change seen
+    pub fn get_mounts(&self) -> &Vec<Mount> {
+        &self.mounts
+    }
+
+    /// Block until the mount table changes and return what changed. A change which is
+    /// undone before the table is reread, e.g. a quick mount and unmount, gives no events
+    pub fn wait(&mut self) -> Result<Vec<MountEvent>, io::Error> {
+        loop {
+            self.poll(-1)?;
+            let events = self.refresh()?;
+            if !events.is_empty() {
+                return Ok(events);
+            }
+        }
+    }
+
+    /// As wait but gives up after timeout_ms milliseconds, returning no events
+    pub fn wait_timeout(&mut self, timeout_ms: i32) -> Result<Vec<MountEvent>, io::Error> {
+        if self.poll(timeout_ms)? {
+            self.refresh()
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    /// Call f for every change until it returns false
+    pub fn watch<F>(mut self, mut f: F) -> Result<(), io::Error>
+    where
+        F: FnMut(&MountEvent) -> bool,
+    {
+        loop {
+            for event in self.wait()? {
+                if !f(&event) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Returns false if the timeout expired without a change
+    fn poll(&self, timeout_ms: i32) -> Result<bool, io::Error> {
+        let mut fds = libc::pollfd {
+            fd: self.file.as_raw_fd(),
+            events: libc::POLLPRI | libc::POLLERR,
+            revents: 0,
+        };
+        loop {
+            let r = unsafe { libc::poll(&mut fds, 1, timeout_ms) };
+            if r >= 0 {
+                return Ok(r > 0);
+            }
+            let e = io::Error::last_os_error();
+            if e.kind() != io::ErrorKind::Interrupted {
+                return Err(e);
+            }
+        }
+    }
+
+    fn refresh(&mut self) -> Result<Vec<MountEvent>, io::Error> {
+        let mounts = MountWatcher::read(&mut self.file)?;
+        let old = ::std::mem::take(&mut self.mounts);
+        let events = diff(old, &mounts);
+        self.mounts = mounts;
+        Ok(events)
+    }
+
+    /// The file has to be read again from the start to see the new table
+    fn read(file: &mut File) -> Result<Vec<Mount>, io::Error> {
+        let mut data = vec![];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_to_end(&mut data)?;
+        Ok(Mounts::parse(&data).get_mounts())
+    }
+}
+
+/// Blocks for each change, the iterator ends if the mount table can no longer be read
+impl Iterator for MountWatcher {
+    type Item = MountEvent;
+
+    fn next(&mut self) -> Option<MountEvent> {
+        while self.pending.is_empty() {
+            match self.wait() {
+                Ok(events) => self.pending.extend(events),
+                Err(_) => return None,
+            }
+        }
+        self.pending.pop_front()
+    }
+}
+
+/// Mounts are matched on device and mount point, a match with different options is a
+/// remount. Mounts stacked on the same point are paired up in table order
+fn diff(old: Vec<Mount>, new: &[Mount]) -> Vec<MountEvent> {
+    let same = |a: &Mount, b: &Mount| a.get_dev() == b.get_dev() && a.get_mnt() == b.get_mnt();
+    let mut old: Vec<Option<Mount>> = old.into_iter().map(Some).collect();
+    let mut matched = vec![false; new.len()];
+    for (i, m) in new.iter().enumerate() {
+        let found = old.iter().position(|o| {
+            o.as_ref()
+                .is_some_and(|o| same(o, m) && o.get_options() == m.get_options())
+        });
+        if let Some(j) = found {
+            old[j] = None;
+            matched[i] = true;
+        }
+    }
+    let mut events = vec![];
+    let mut added = vec![];
+    for (i, m) in new.iter().enumerate() {
+        if matched[i] {
+            continue;
+        }
+        match old.iter().position(|o| o.as_ref().is_some_and(|o| same(o, m))) {
+            Some(j) => events.push(MountEvent::Remounted {
+                old: old[j].take().unwrap(),
+                new: m.clone(),
+            }),
+            None => added.push(MountEvent::Added(m.clone())),
+        }
+    }
+    events.extend(old.into_iter().flatten().map(MountEvent::Removed));
+    events.extend(added);
+    events
+}
+
+#[test]
+fn test_mount_diff() {
+    let before = Mounts::parse(
+        b"/dev/sda1 / ext4 rw,relatime 0 0
+/dev/sdb1 /data xfs rw,noatime 0 0
+/dev/sdc1 /backup ext4 rw,relatime 0 0
+",
+    ).get_mounts();
+    let after = Mounts::parse(
+        b"/dev/sda1 / ext4 rw,relatime 0 0
+/dev/sdb1 /data xfs ro,noatime 0 0
+/dev/sdd1 /mnt/usb\\040key vfat rw 0 0
+",
+    ).get_mounts();
+    let events = diff(before.clone(), &after);
+    assert_eq!(events.len(), 3);
+    assert_eq!(
+        events[0],
+        MountEvent::Remounted {
+            old: before[1].clone(),
+            new: after[1].clone(),
+        }
+    );
+    assert_eq!(events[1], MountEvent::Removed(before[2].clone()));
+    assert_eq!(events[2], MountEvent::Added(after[2].clone()));
+    assert!(diff(after.clone(), &after).is_empty());
+}
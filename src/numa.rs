@@ -0,0 +1,512 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use cpu::{parse_cpu_list, Cpus};
+use process::{stat_field, PROC};
+
+pub static SYS_NODE: &str = "/sys/devices/system/node";
+
+/// Memory of one NUMA node from node<N>/meminfo, sizes in kB
+#[derive(Debug)]
+pub struct NodeMemInfo {
+    mem_total: u64,
+    mem_free: u64,
+    mem_used: u64,
+    file_pages: u64,
+    anon_pages: u64,
+    other: HashMap<String, u64>,
+}
+
+impl PartialEq for NodeMemInfo {
+    fn eq(&self, other: &NodeMemInfo) -> bool {
+        (self.mem_total == other.mem_total) & (self.mem_free == other.mem_free)
+            & (self.mem_used == other.mem_used) & (self.file_pages == other.file_pages)
+            & (self.anon_pages == other.anon_pages) & (self.other == other.other)
+    }
+}
+
+impl NodeMemInfo {
+    pub fn get_mem_total(&self) -> u64 {
+        self.mem_total
+    }
+
+    pub fn get_mem_free(&self) -> u64 {
+        self.mem_free
+    }
+
+    pub fn get_mem_used(&self) -> u64 {
+        self.mem_used
+    }
+
+    pub fn get_file_pages(&self) -> u64 {
+        self.file_pages
+    }
+
+    pub fn get_anon_pages(&self) -> u64 {
+        self.anon_pages
+    }
+
+    /// Any key without a dedicated field, e.g. "Dirty" or "HugePages_Free"
+    pub fn get(&self, key: &str) -> Option<u64> {
+        self.other.get(key).cloned()
+    }
+
+    pub fn used_percent(&self) -> f64 {
+        if self.mem_total == 0 {
+            return 0.0;
+        }
+        self.mem_used as f64 / self.mem_total as f64 * 100.0
+    }
+
+    /// Lines look like "Node 0 MemTotal:  16303764 kB"
+    fn parse(s: &str) -> NodeMemInfo {
+        let mut values: HashMap<String, u64> = HashMap::new();
+        for line in s.lines() {
+            let split: Vec<&str> = line.split_whitespace().collect();
+            if split.len() < 4 {
+                continue;
+            }
+            let key = split[2].trim_end_matches(':').to_string();
+            values.insert(key, split[3].parse::<u64>().unwrap_or(0));
+        }
+        let mut take = |key: &str| values.remove(key).unwrap_or(0);
+        NodeMemInfo {
+            mem_total: take("MemTotal"),
+            mem_free: take("MemFree"),
+            mem_used: take("MemUsed"),
+            file_pages: take("FilePages"),
+            anon_pages: take("AnonPages"),
+            other: values,
+        }
+    }
+}
+
+/// Page allocation counters of one node from node<N>/numastat
+#[derive(Debug)]
+pub struct NumaStat {
+    numa_hit: u64,
+    numa_miss: u64,
+    numa_foreign: u64,
+    interleave_hit: u64,
+    local_node: u64,
+    other_node: u64,
+}
+
+impl PartialEq for NumaStat {
+    fn eq(&self, other: &NumaStat) -> bool {
+        (self.numa_hit == other.numa_hit) & (self.numa_miss == other.numa_miss)
+            & (self.numa_foreign == other.numa_foreign)
+            & (self.interleave_hit == other.interleave_hit)
+            & (self.local_node == other.local_node) & (self.other_node == other.other_node)
+    }
+}
+
+impl NumaStat {
+    /// Pages allocated on this node as intended
+    pub fn get_numa_hit(&self) -> u64 {
+        self.numa_hit
+    }
+
+    /// Pages allocated here although another node was preferred
+    pub fn get_numa_miss(&self) -> u64 {
+        self.numa_miss
+    }
+
+    /// Pages meant for this node that ended up elsewhere
+    pub fn get_numa_foreign(&self) -> u64 {
+        self.numa_foreign
+    }
+
+    pub fn get_interleave_hit(&self) -> u64 {
+        self.interleave_hit
+    }
+
+    /// Pages allocated here for a task running on this node
+    pub fn get_local_node(&self) -> u64 {
+        self.local_node
+    }
+
+    /// Pages allocated here for a task running on another node
+    pub fn get_other_node(&self) -> u64 {
+        self.other_node
+    }
+
+    /// Percentage of allocations on this node that weren't where they were meant to be
+    pub fn miss_percent(&self) -> f64 {
+        let total = self.numa_hit + self.numa_miss;
+        if total == 0 {
+            return 0.0;
+        }
+        self.numa_miss as f64 / total as f64 * 100.0
+    }
+
+    fn parse(s: &str) -> NumaStat {
+        let mut values: HashMap<&str, u64> = HashMap::new();
+        for line in s.lines() {
+            let mut split = line.split_whitespace();
+            if let (Some(k), Some(v)) = (split.next(), split.next()) {
+                values.insert(k, v.parse::<u64>().unwrap_or(0));
+            }
+        }
+        let get = |key: &str| values.get(key).cloned().unwrap_or(0);
+        NumaStat {
+            numa_hit: get("numa_hit"),
+            numa_miss: get("numa_miss"),
+            numa_foreign: get("numa_foreign"),
+            interleave_hit: get("interleave_hit"),
+            local_node: get("local_node"),
+            other_node: get("other_node"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct NumaNode {
+    node: usize,
+    cpus: Vec<usize>,
+    meminfo: NodeMemInfo,
+    numastat: NumaStat,
+}
+
+impl PartialEq for NumaNode {
+    fn eq(&self, other: &NumaNode) -> bool {
+        (self.node == other.node) & (self.cpus == other.cpus) & (self.meminfo == other.meminfo)
+            & (self.numastat == other.numastat)
+    }
+}
+
+impl fmt::Display for NumaNode {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "Node {}: CPUs: {} Total: {} kB Free: {} kB Miss: {:.1}%",
+            self.node,
+            self.cpus.len(),
+            self.meminfo.mem_total,
+            self.meminfo.mem_free,
+            self.numastat.miss_percent()
+        )
+    }
+}
+
+impl NumaNode {
+    pub fn get_node(&self) -> usize {
+        self.node
+    }
+
+    /// CPUs local to the node, empty for memory only nodes such as CXL memory
+    pub fn get_cpus(&self) -> &Vec<usize> {
+        &self.cpus
+    }
+
+    pub fn get_meminfo(&self) -> &NodeMemInfo {
+        &self.meminfo
+    }
+
+    pub fn get_numastat(&self) -> &NumaStat {
+        &self.numastat
+    }
+
+    fn read(dir: &Path, node: usize) -> Result<NumaNode, io::Error> {
+        Ok(NumaNode {
+            node,
+            cpus: parse_cpu_list(&fs::read_to_string(dir.join("cpulist"))?),
+            meminfo: NodeMemInfo::parse(&fs::read_to_string(dir.join("meminfo"))?),
+            numastat: NumaStat::parse(&fs::read_to_string(dir.join("numastat"))?),
+        })
+    }
+}
+
+/// NUMA nodes from /sys/devices/system/node. A NUMA kernel lists node0 even on a single
+/// socket machine, without CONFIG_NUMA the directory is missing and there are no nodes
+#[derive(Debug)]
+pub struct NumaNodes {
+    nodes: Vec<NumaNode>,
+}
+
+impl NumaNodes {
+    pub fn get_nodes(self) -> Vec<NumaNode> {
+        self.nodes
+    }
+
+    pub fn new() -> Result<NumaNodes, io::Error> {
+        NumaNodes::read(Path::new(SYS_NODE))
+    }
+
+    pub fn get(&self, node: usize) -> Option<&NumaNode> {
+        self.nodes.iter().find(|n| n.node == node)
+    }
+
+    /// The node a CPU belongs to
+    pub fn node_of_cpu(&self, cpu: usize) -> Option<usize> {
+        self.nodes.iter().find(|n| n.cpus.contains(&cpu)).map(|n| n.node)
+    }
+
+    fn read(root: &Path) -> Result<NumaNodes, io::Error> {
+        let mut nodes = vec![];
+        let entries = match fs::read_dir(root) {
+            Ok(entries) => entries,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(NumaNodes { nodes })
+            }
+            Err(e) => return Err(e),
+        };
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if let Some(node) = name.strip_prefix("node").and_then(|n| n.parse().ok()) {
+                nodes.push(NumaNode::read(&entry.path(), node)?);
+            }
+        }
+        nodes.sort_by_key(|n| n.node);
+        Ok(NumaNodes { nodes })
+    }
+}
+
+/// One mapping from /proc/<pid>/numa_maps
+#[derive(Debug)]
+pub struct NumaMapping {
+    address: u64,
+    policy: String,
+    file: Option<String>,
+    pages: HashMap<usize, u64>,
+    page_size_kb: u64,
+}
+
+impl PartialEq for NumaMapping {
+    fn eq(&self, other: &NumaMapping) -> bool {
+        (self.address == other.address) & (self.policy == other.policy)
+            & (self.file == other.file) & (self.pages == other.pages)
+            & (self.page_size_kb == other.page_size_kb)
+    }
+}
+
+impl NumaMapping {
+    /// Start address of the mapping
+    pub fn get_address(&self) -> u64 {
+        self.address
+    }
+
+    /// Memory policy, e.g. "default", "bind:0", "interleave:0-1", "prefer:1"
+    pub fn get_policy(&self) -> &str {
+        &self.policy
+    }
+
+    /// Backing file, None for anonymous memory
+    pub fn get_file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    /// Resident pages on each node
+    pub fn get_pages(&self) -> &HashMap<usize, u64> {
+        &self.pages
+    }
+
+    pub fn get_page_size_kb(&self) -> u64 {
+        self.page_size_kb
+    }
+
+    fn parse(line: &str) -> Option<NumaMapping> {
+        let mut fields = line.split_whitespace();
+        let address = u64::from_str_radix(fields.next()?, 16).ok()?;
+        let policy = fields.next()?.to_string();
+        let mut mapping = NumaMapping {
+            address,
+            policy,
+            file: None,
+            pages: HashMap::new(),
+            // Missing on kernels before 2.6.36, which only had base pages
+            page_size_kb: 4,
+        };
+        for field in fields {
+            let (key, value) = match field.find('=') {
+                Some(i) => (&field[..i], &field[i + 1..]),
+                None => continue,
+            };
+            if key == "file" {
+                mapping.file = Some(value.to_string());
+            } else if key == "kernelpagesize_kB" {
+                mapping.page_size_kb = value.parse::<u64>().unwrap_or(4);
+            } else if let Some(node) = key.strip_prefix('N').and_then(|n| n.parse().ok()) {
+                mapping.pages.insert(node, value.parse::<u64>().unwrap_or(0));
+            }
+        }
+        Some(mapping)
+    }
+}
+
+/// NUMA placement of a process's memory
+#[derive(Debug)]
+pub struct NumaMaps {
+    mappings: Vec<NumaMapping>,
+}
+
+impl NumaMaps {
+    pub fn get_mappings(self) -> Vec<NumaMapping> {
+        self.mappings
+    }
+
+    pub fn pid(pid: &str) -> Result<NumaMaps, io::Error> {
+        let s = fs::read_to_string(format!("{}/{}/numa_maps", PROC, pid))?;
+        Ok(NumaMaps::parse(&s))
+    }
+
+    /// Resident memory on each node in kB, sorted by node
+    pub fn per_node(&self) -> Vec<(usize, u64)> {
+        let mut nodes: HashMap<usize, u64> = HashMap::new();
+        for m in &self.mappings {
+            for (node, pages) in &m.pages {
+                *nodes.entry(*node).or_insert(0) += pages * m.page_size_kb;
+            }
+        }
+        let mut nodes: Vec<(usize, u64)> = nodes.into_iter().collect();
+        nodes.sort();
+        nodes
+    }
+
+    /// Split resident memory into kB on the home node and kB on every other node
+    pub fn placement(&self, home: usize) -> NumaPlacement {
+        let mut placement = NumaPlacement {
+            home,
+            local: 0,
+            remote: 0,
+        };
+        for (node, kb) in self.per_node() {
+            if node == home {
+                placement.local += kb
+            } else {
+                placement.remote += kb
+            }
+        }
+        placement
+    }
+
+    fn parse(s: &str) -> NumaMaps {
+        NumaMaps {
+            mappings: s.lines().filter_map(NumaMapping::parse).collect(),
+        }
+    }
+}
+
+/// Local versus remote memory of a process, sizes in kB
+#[derive(Debug)]
+pub struct NumaPlacement {
+    home: usize,
+    local: u64,
+    remote: u64,
+}
+
+impl PartialEq for NumaPlacement {
+    fn eq(&self, other: &NumaPlacement) -> bool {
+        (self.home == other.home) & (self.local == other.local) & (self.remote == other.remote)
+    }
+}
+
+impl fmt::Display for NumaPlacement {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "Node: {} Local: {} kB Remote: {} kB ({:.1}%)",
+            self.home,
+            self.local,
+            self.remote,
+            self.remote_percent()
+        )
+    }
+}
+
+impl NumaPlacement {
+    /// The node memory is counted as local to
+    pub fn get_home(&self) -> usize {
+        self.home
+    }
+
+    pub fn get_local(&self) -> u64 {
+        self.local
+    }
+
+    pub fn get_remote(&self) -> u64 {
+        self.remote
+    }
+
+    pub fn remote_percent(&self) -> f64 {
+        let total = self.local + self.remote;
+        if total == 0 {
+            return 0.0;
+        }
+        self.remote as f64 / total as f64 * 100.0
+    }
+
+    /// Placement of PID relative to the node of the CPU it last ran on. A process the
+    /// scheduler has moved will show its memory as remote until it is migrated too
+    pub fn pid(pid: &str) -> Result<NumaPlacement, io::Error> {
+        let maps = NumaMaps::pid(pid)?;
+        let stat = fs::read_to_string(format!("{}/{}/stat", PROC, pid))?;
+        let home = stat_field(&stat, 39)
+            .and_then(|cpu| cpu.parse::<usize>().ok())
+            .and_then(|cpu| Cpus::new().get(cpu).and_then(|c| c.get_node()))
+            .unwrap_or(0);
+        Ok(maps.placement(home))
+    }
+}
+
+#[test]
+fn test_numa_parse() {
+    let meminfo = NodeMemInfo::parse(
+        "Node 1 MemTotal:       16303764 kB
+Node 1 MemFree:         1263384 kB
+Node 1 MemUsed:        15040380 kB
+Node 1 AnonPages:       8000000 kB
+Node 1 HugePages_Free:      0
+",
+    );
+    assert_eq!(meminfo.get_mem_used(), 15040380);
+    assert_eq!(meminfo.get("HugePages_Free"), Some(0));
+
+    let numastat = NumaStat::parse("numa_hit 900\nnuma_miss 100\nnuma_foreign 5\nother_node 7\n");
+    assert_eq!(numastat.miss_percent(), 10.0);
+    assert_eq!(numastat.get_other_node(), 7);
+
+    let maps = NumaMaps::parse(
+        "00400000 default file=/usr/sbin/mysqld mapped=100 N0=60 N1=40 kernelpagesize_kB=4
+7f0000000000 interleave:0-1 anon=1024 dirty=1024 N0=512 N1=512 kernelpagesize_kB=4
+7f2000000000 bind:1 huge anon=4 dirty=4 N1=4 kernelpagesize_kB=2048
+",
+    );
+    assert_eq!(maps.per_node(), vec![(0, 2288), (1, 10400)]);
+    assert_eq!(
+        maps.placement(1),
+        NumaPlacement {
+            home: 1,
+            local: 10400,
+            remote: 2288,
+        }
+    );
+    let mappings = maps.get_mappings();
+    assert_eq!(mappings[0].get_file(), Some("/usr/sbin/mysqld"));
+    assert_eq!(mappings[1].get_policy(), "interleave:0-1");
+    assert_eq!(mappings[2].get_page_size_kb(), 2048);
+}
+
+#[test]
+fn test_numa_nodes_read() {
+    use testtree::TestTree;
+
+    let tree = TestTree::new("numa");
+    assert!(NumaNodes::read(&tree.join("missing")).unwrap().get_nodes().is_empty());
+    tree.write_all(&[
+        ("node0/cpulist", "0-3\n"),
+        ("node0/meminfo", "Node 0 MemTotal:       16303764 kB\n"),
+        ("node0/numastat", "numa_hit 900\n"),
+        ("node1/cpulist", "\n"),
+        ("node1/meminfo", "Node 1 MemTotal:       33554432 kB\n"),
+        ("node1/numastat", "numa_hit 5\n"),
+        ("possible", "0-1\n"),
+    ]);
+    let nodes = NumaNodes::read(tree.path()).unwrap();
+    assert_eq!(nodes.node_of_cpu(2), Some(0));
+    assert!(nodes.get(1).unwrap().get_cpus().is_empty());
+    assert_eq!(nodes.get_nodes().len(), 2);
+}
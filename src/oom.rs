@@ -0,0 +1,186 @@
+use process::{all_status, status_name, ProcessOom};
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use vmstat::VMSTAT;
+
+pub static CGROUP: &str = "/sys/fs/cgroup";
+
+#[derive(Debug)]
+pub struct OomCandidate {
+    pid: String,
+    comm: String,
+    oom: ProcessOom,
+}
+
+impl PartialEq for OomCandidate {
+    fn eq(&self, other: &OomCandidate) -> bool {
+        (self.pid == other.pid) & (self.comm == other.comm) & (self.oom == other.oom)
+    }
+}
+
+impl fmt::Display for OomCandidate {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmtr, "PID: {} {} {}", self.pid, self.comm, self.oom)
+    }
+}
+
+impl OomCandidate {
+    pub fn get_pid(&self) -> String {
+        self.pid.clone()
+    }
+
+    pub fn get_comm(&self) -> String {
+        self.comm.clone()
+    }
+
+    pub fn get_oom(&self) -> &ProcessOom {
+        &self.oom
+    }
+}
+
+/// Processes ordered by how likely the kernel is to pick them when it runs out of memory
+#[derive(Debug)]
+pub struct OomRanking {
+    candidates: Vec<OomCandidate>,
+}
+
+impl OomRanking {
+    pub fn get_candidates(self) -> Vec<OomCandidate> {
+        self.candidates
+    }
+
+    /// Rank every killable process on the system, processes which exit while the ranking
+    /// is being built are skipped
+    pub fn new() -> OomRanking {
+        let mut candidates = vec![];
+        for (pid, status) in all_status() {
+            let oom = match ProcessOom::with_status(&pid, &status) {
+                Ok(o) => o,
+                Err(_) => continue,
+            };
+            let comm = status_name(&status);
+            candidates.push(OomCandidate { pid, comm, oom })
+        }
+        OomRanking::rank(candidates)
+    }
+
+    /// oom_score already folds in RSS, swap and oom_score_adj the way the kernel badness
+    /// heuristic does, ties are broken on RSS plus swap
+    fn rank(mut candidates: Vec<OomCandidate>) -> OomRanking {
+        candidates.retain(|c| c.oom.is_killable());
+        candidates.sort_by(|a, b| {
+            b.oom.get_oom_score().cmp(&a.oom.get_oom_score()).then(
+                (b.oom.get_rss() + b.oom.get_swap()).cmp(&(a.oom.get_rss() + a.oom.get_swap())),
+            )
+        });
+        OomRanking { candidates }
+    }
+}
+
+/// OOM kill counters, system wide from vmstat or per cgroup from memory.events
+#[derive(Debug)]
+pub struct OomEvents {
+    oom: Option<u64>,
+    oom_kill: u64,
+}
+
+impl PartialEq for OomEvents {
+    fn eq(&self, other: &OomEvents) -> bool {
+        (self.oom == other.oom) & (self.oom_kill == other.oom_kill)
+    }
+}
+
+impl fmt::Display for OomEvents {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(oom) = self.oom {
+            write!(fmtr, "OOM: {} ", oom)?;
+        }
+        write!(fmtr, "OOM kills: {}", self.oom_kill)
+    }
+}
+
+impl OomEvents {
+    /// Number of times the cgroup hit its limit and the OOM killer was invoked, None
+    /// system wide as vmstat doesn't track it
+    pub fn get_oom(&self) -> Option<u64> {
+        self.oom
+    }
+
+    pub fn get_oom_kill(&self) -> u64 {
+        self.oom_kill
+    }
+
+    /// System wide kills, oom_kill was added to vmstat in Linux 4.13
+    pub fn system() -> Result<OomEvents, io::Error> {
+        let s = OomEvents::fetch(VMSTAT)?;
+        Ok(OomEvents {
+            oom: None,
+            oom_kill: OomEvents::value(&s, "oom_kill"),
+        })
+    }
+
+    /// Kills for a cgroup v2 path relative to the cgroup root, e.g. "system.slice"
+    pub fn cgroup(path: &str) -> Result<OomEvents, io::Error> {
+        let s = OomEvents::fetch(&format!("{}/{}/memory.events", CGROUP, path))?;
+        Ok(OomEvents::parse(&s))
+    }
+
+    fn fetch(path: &str) -> Result<String, io::Error> {
+        let mut s = String::new();
+        let mut f = File::open(path)?;
+        f.read_to_string(&mut s)?;
+        Ok(s)
+    }
+
+    fn parse(s: &str) -> OomEvents {
+        OomEvents {
+            oom: Some(OomEvents::value(s, "oom")),
+            oom_kill: OomEvents::value(s, "oom_kill"),
+        }
+    }
+
+    fn value(s: &str, key: &str) -> u64 {
+        for line in s.lines() {
+            let mut split = line.split_whitespace();
+            if split.next() == Some(key) {
+                return split.next().map_or(0, |v| v.parse::<u64>().unwrap_or(0));
+            }
+        }
+        0
+    }
+}
+
+#[test]
+fn test_memory_events_parse() {
+    let events = "low 0\nhigh 12\nmax 340\noom 4\noom_kill 3\noom_group_kill 0\n";
+    let test = OomEvents {
+        oom: Some(4),
+        oom_kill: 3,
+    };
+    assert_eq!(OomEvents::parse(events), test)
+}
+
+#[test]
+fn test_oom_rank() {
+    let candidate = |pid: &str, score: &str, adj: &str, rss: u64, swap: u64| OomCandidate {
+        pid: pid.to_string(),
+        comm: format!("proc {}", pid),
+        oom: ProcessOom::parse(
+            score,
+            adj,
+            "0",
+            &format!("Name:\tproc\nVmRSS:\t{} kB\nVmSwap:\t{} kB\n", rss, swap),
+        ),
+    };
+    let ranking = OomRanking::rank(vec![
+        candidate("10", "200", "0", 1000, 0),
+        candidate("11", "900", "-1000", 90000, 0),
+        candidate("12", "650", "0", 4000, 500),
+        candidate("13", "650", "0", 3000, 2000),
+        candidate("14", "0", "0", 0, 0),
+    ]);
+    let pids: Vec<String> = ranking.get_candidates().iter().map(|c| c.get_pid()).collect();
+    assert_eq!(pids, vec!["13", "12", "10", "14"]);
+}
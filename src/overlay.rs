@@ -0,0 +1,326 @@
+use std::collections::HashSet;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use mounts::{unescape, Mount, MountUsage, Mounts};
+
+/// Where docker's overlay2 driver keeps layers, lowerdir entries may be given relative to
+/// it as l/<short id> to keep the mount options under a page
+pub static DOCKER_OVERLAY2: &str = "/var/lib/docker/overlay2";
+
+/// Storage paths of the container runtimes, the layer id is the path component after them
+static RUNTIMES: &[(Runtime, &str)] = &[
+    (Runtime::Docker, "/var/lib/docker/overlay2/"),
+    (
+        Runtime::Containerd,
+        "/var/lib/containerd/io.containerd.snapshotter.v1.overlayfs/snapshots/",
+    ),
+    (Runtime::Podman, "/var/lib/containers/storage/overlay/"),
+];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Runtime {
+    Docker,
+    Containerd,
+    Podman,
+}
+
+/// A layer belonging to a container runtime's storage
+#[derive(Debug, PartialEq, Clone)]
+pub struct ContainerLayer {
+    runtime: Runtime,
+    id: String,
+}
+
+impl ContainerLayer {
+    pub fn get_runtime(&self) -> Runtime {
+        self.runtime
+    }
+
+    /// Layer directory name, a snapshot number for containerd
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    /// Docker puts /etc/hosts and friends in a <id>-init layer between the image and
+    /// the container's writable layer
+    pub fn is_init(&self) -> bool {
+        self.id.ends_with("-init")
+    }
+
+    fn identify(path: &Path) -> Option<ContainerLayer> {
+        let path = path.to_string_lossy();
+        for &(runtime, root) in RUNTIMES {
+            if let Some(rest) = path.strip_prefix(root) {
+                let id = rest.split('/').next().unwrap_or("");
+                if !id.is_empty() && id != "l" {
+                    return Some(ContainerLayer {
+                        runtime,
+                        id: id.to_string(),
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+/// One directory of an overlay, the upper layer first then the lower layers top down
+#[derive(Debug)]
+pub struct OverlayLayer {
+    path: PathBuf,
+    upper: bool,
+    container: Option<ContainerLayer>,
+}
+
+impl PartialEq for OverlayLayer {
+    fn eq(&self, other: &OverlayLayer) -> bool {
+        (self.path == other.path) & (self.upper == other.upper)
+            & (self.container == other.container)
+    }
+}
+
+impl fmt::Display for OverlayLayer {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "{}: {}",
+            if self.upper { "Upper" } else { "Lower" },
+            self.path.display()
+        )
+    }
+}
+
+impl OverlayLayer {
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The writable layer, for a container this holds everything it has changed
+    pub fn is_upper(&self) -> bool {
+        self.upper
+    }
+
+    /// The runtime layer the directory belongs to, None outside runtime storage
+    pub fn get_container(&self) -> Option<&ContainerLayer> {
+        self.container.as_ref()
+    }
+
+    /// Lower layers of a runtime other than docker's init layer come from images
+    pub fn is_image_layer(&self) -> bool {
+        !self.upper && self.container.as_ref().is_some_and(|c| !c.is_init())
+    }
+
+    /// Disk space used by the layer directory in bytes, counted the way du does: allocated
+    /// blocks, hard links once and without crossing into other filesystems
+    pub fn size(&self) -> Result<u64, io::Error> {
+        let dev = fs::symlink_metadata(&self.path)?.dev();
+        let mut seen = HashSet::new();
+        let mut size = 0;
+        let mut todo = vec![self.path.clone()];
+        while let Some(p) = todo.pop() {
+            let meta = match fs::symlink_metadata(&p) {
+                Ok(m) => m,
+                // Files come and go under a running container
+                Err(_) => continue,
+            };
+            if meta.dev() != dev || !seen.insert(meta.ino()) {
+                continue;
+            }
+            size += meta.blocks() * 512;
+            if meta.is_dir() {
+                if let Ok(entries) = fs::read_dir(&p) {
+                    todo.extend(entries.filter_map(|e| e.ok()).map(|e| e.path()));
+                }
+            }
+        }
+        Ok(size)
+    }
+
+    /// Capacity of the filesystem the layer is stored on
+    pub fn usage(&self) -> Result<MountUsage, io::Error> {
+        MountUsage::statvfs(&self.path)
+    }
+}
+
+/// An overlay mount with its layer directories decoded from the mount options
+#[derive(Debug)]
+pub struct Overlay {
+    mnt: PathBuf,
+    upper: Option<PathBuf>,
+    work: Option<PathBuf>,
+    lower: Vec<PathBuf>,
+}
+
+impl PartialEq for Overlay {
+    fn eq(&self, other: &Overlay) -> bool {
+        (self.mnt == other.mnt) & (self.upper == other.upper) & (self.work == other.work)
+            & (self.lower == other.lower)
+    }
+}
+
+impl fmt::Display for Overlay {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "Overlay: {} Layers: {}",
+            self.mnt.display(),
+            self.lower.len() + self.upper.iter().count()
+        )
+    }
+}
+
+impl Overlay {
+    /// Decode an overlay mount, None for other filesystem types
+    pub fn from_mount(mount: &Mount) -> Option<Overlay> {
+        if mount.get_fs_type() != "overlay" {
+            return None;
+        }
+        let opts = mount.get_options();
+        let path = |key: &str| opts.get_raw(key).map(|v| resolve(unescape(v.as_bytes())));
+        Some(Overlay {
+            mnt: mount.get_mnt().to_path_buf(),
+            upper: path("upperdir"),
+            work: path("workdir"),
+            lower: opts.get_raw("lowerdir")
+                .map(|v| split_lower(v).into_iter().map(resolve).collect())
+                .unwrap_or_default(),
+        })
+    }
+
+    pub fn get_mnt(&self) -> &Path {
+        &self.mnt
+    }
+
+    /// None for a read only overlay made only of lower layers
+    pub fn get_upper(&self) -> Option<&Path> {
+        self.upper.as_deref()
+    }
+
+    pub fn get_work(&self) -> Option<&Path> {
+        self.work.as_deref()
+    }
+
+    /// Lower directories top down, the order lowerdir lists them
+    pub fn get_lower(&self) -> &Vec<PathBuf> {
+        &self.lower
+    }
+
+    /// Every layer top down, starting with the upper layer if there is one
+    pub fn layers(&self) -> Vec<OverlayLayer> {
+        let layer = |path: &PathBuf, upper: bool| OverlayLayer {
+            path: path.clone(),
+            upper,
+            container: ContainerLayer::identify(path),
+        };
+        let mut layers: Vec<OverlayLayer> = self.upper.iter().map(|p| layer(p, true)).collect();
+        layers.extend(self.lower.iter().map(|p| layer(p, false)));
+        layers
+    }
+}
+
+#[derive(Debug)]
+pub struct Overlays {
+    overlays: Vec<Overlay>,
+}
+
+impl Overlays {
+    pub fn get_overlays(self) -> Vec<Overlay> {
+        self.overlays
+    }
+
+    pub fn new() -> Overlays {
+        Overlays {
+            overlays: Mounts::new()
+                .get_mounts()
+                .iter()
+                .filter_map(Overlay::from_mount)
+                .collect(),
+        }
+    }
+}
+
+/// lowerdir is a colon separated list, a colon inside a path is escaped with a backslash
+fn split_lower(v: &OsStr) -> Vec<PathBuf> {
+    let bytes = unescape(v.as_bytes());
+    let mut dirs = vec![];
+    let mut current = vec![];
+    let mut bytes = bytes.as_os_str().as_bytes().iter();
+    while let Some(&b) = bytes.next() {
+        match b {
+            b'\\' => current.extend(bytes.next()),
+            b':' => dirs.push(PathBuf::from(OsString::from_vec(::std::mem::take(&mut current)))),
+            _ => current.push(b),
+        }
+    }
+    dirs.push(PathBuf::from(OsString::from_vec(current)));
+    dirs.retain(|d| !d.as_os_str().is_empty());
+    dirs
+}
+
+/// Docker's short l/<id> links are relative to its overlay2 directory, follow them so
+/// the layer can be identified
+fn resolve(path: PathBuf) -> PathBuf {
+    if path.is_absolute() {
+        return path;
+    }
+    let full = Path::new(DOCKER_OVERLAY2).join(&path);
+    fs::canonicalize(&full).unwrap_or(full)
+}
+
+#[test]
+fn test_overlay_layers() {
+    use testtree::TestTree;
+
+    let mounts = Mounts::parse(
+        b"overlay /var/lib/docker/overlay2/9f1c/merged overlay rw,relatime,lowerdir=/var/lib/docker/overlay2/9f1c-init/diff:/var/lib/docker/overlay2/a1b2/diff:/opt/base\\134:1,upperdir=/var/lib/docker/overlay2/9f1c/diff,workdir=/var/lib/docker/overlay2/9f1c/work 0 0
+/dev/sda1 / ext4 rw 0 0
+",
+    ).get_mounts();
+    assert!(Overlay::from_mount(&mounts[1]).is_none());
+    let overlay = Overlay::from_mount(&mounts[0]).unwrap();
+    assert_eq!(overlay.get_upper(), Some(Path::new("/var/lib/docker/overlay2/9f1c/diff")));
+    assert_eq!(
+        overlay.get_lower(),
+        &vec![
+            PathBuf::from("/var/lib/docker/overlay2/9f1c-init/diff"),
+            PathBuf::from("/var/lib/docker/overlay2/a1b2/diff"),
+            PathBuf::from("/opt/base:1"),
+        ]
+    );
+    // paths need not be UTF-8, the kernel prints their bytes as they are
+    let raw = Mounts::parse(b"overlay /merged overlay rw,lowerdir=/opt/caf\xe9:/opt/base 0 0\n");
+    assert_eq!(
+        Overlay::from_mount(&raw.get_mounts()[0]).unwrap().get_lower()[0].as_os_str(),
+        OsStr::from_bytes(b"/opt/caf\xe9")
+    );
+
+    let layers = overlay.layers();
+    assert_eq!(layers.len(), 4);
+    assert!(layers[0].is_upper());
+    assert!(!layers[1].is_image_layer());
+    assert!(layers[2].is_image_layer());
+    assert_eq!(
+        layers[2].get_container(),
+        Some(&ContainerLayer {
+            runtime: Runtime::Docker,
+            id: "a1b2".to_string(),
+        })
+    );
+    assert_eq!(layers[3].get_container(), None);
+
+    let tree = TestTree::new("overlay");
+    tree.write("etc/big", vec![1u8; 64 * 1024]);
+    fs::hard_link(tree.join("etc/big"), tree.join("big-link")).unwrap();
+    let layer = OverlayLayer {
+        path: tree.path().to_path_buf(),
+        upper: true,
+        container: None,
+    };
+    let size = layer.size().unwrap();
+    assert!((64 * 1024..2 * 64 * 1024).contains(&size));
+}
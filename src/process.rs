@@ -73,6 +73,147 @@ impl ProcessMemory {
     }
 }
 
+#[derive(Debug)]
+pub struct ProcessOom {
+    oom_score: i64,
+    oom_score_adj: i64,
+    oom_adj: i64,
+    vm_rss: u64,
+    vm_swap: u64,
+}
+
+impl PartialEq for ProcessOom {
+    fn eq(&self, other: &ProcessOom) -> bool {
+        (self.oom_score == other.oom_score) & (self.oom_score_adj == other.oom_score_adj)
+            & (self.oom_adj == other.oom_adj) & (self.vm_rss == other.vm_rss)
+            & (self.vm_swap == other.vm_swap)
+    }
+}
+
+impl fmt::Display for ProcessOom {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "OOM score: {} Adj: {} Legacy adj: {} RSS: {} kB Swap: {} kB",
+            self.oom_score, self.oom_score_adj, self.oom_adj, self.vm_rss, self.vm_swap
+        )
+    }
+}
+
+impl ProcessOom {
+    /// Fetch OOM killer info for PID
+    pub fn new(p: &str) -> ProcessOom {
+        ProcessOom::fetch(p).unwrap()
+    }
+
+    pub fn get_oom_score(&self) -> i64 {
+        self.oom_score
+    }
+
+    pub fn get_oom_score_adj(&self) -> i64 {
+        self.oom_score_adj
+    }
+
+    pub fn get_oom_adj(&self) -> i64 {
+        self.oom_adj
+    }
+
+    /// Resident set size in kB
+    pub fn get_rss(&self) -> u64 {
+        self.vm_rss
+    }
+
+    /// Swapped out anonymous memory in kB
+    pub fn get_swap(&self) -> u64 {
+        self.vm_swap
+    }
+
+    /// An oom_score_adj of -1000 disables OOM killing for the process
+    pub fn is_killable(&self) -> bool {
+        self.oom_score_adj > -1000
+    }
+
+    /// Reads the oom files and the status file for PID
+    pub(crate) fn fetch(pid: &str) -> Result<ProcessOom, io::Error> {
+        let status = ProcessOom::read(pid, "status")?;
+        ProcessOom::with_status(pid, &status)
+    }
+
+    /// Reads the oom files for PID, reusing a status file that was already read
+    pub(crate) fn with_status(pid: &str, status: &str) -> Result<ProcessOom, io::Error> {
+        let score = ProcessOom::read(pid, "oom_score")?;
+        let score_adj = ProcessOom::read(pid, "oom_score_adj")?;
+        // oom_adj is deprecated and may be missing on newer kernels
+        let adj = ProcessOom::read(pid, "oom_adj").unwrap_or_else(|_| "0".to_string());
+        Ok(ProcessOom::parse(&score, &score_adj, &adj, status))
+    }
+
+    fn read(pid: &str, file: &str) -> Result<String, io::Error> {
+        let path = format!("{}/{}/{}", PROC, pid, file);
+        let mut s = String::new();
+        let mut f = fs::File::open(path)?;
+        f.read_to_string(&mut s)?;
+        Ok(s)
+    }
+
+    pub(crate) fn parse(score: &str, score_adj: &str, adj: &str, status: &str) -> ProcessOom {
+        ProcessOom {
+            oom_score: score.trim().parse::<i64>().unwrap(),
+            oom_score_adj: score_adj.trim().parse::<i64>().unwrap(),
+            oom_adj: adj.trim().parse::<i64>().unwrap(),
+            vm_rss: status_kb(status, "VmRSS"),
+            vm_swap: status_kb(status, "VmSwap"),
+        }
+    }
+}
+
+/// The status file of every process, processes which exit while they are being read are
+/// skipped
+pub(crate) fn all_status() -> Vec<(String, String)> {
+    Processes::new()
+        .processes()
+        .into_iter()
+        .filter_map(|pid| {
+            fs::read_to_string(format!("{}/{}/status", PROC, pid))
+                .ok()
+                .map(|s| (pid, s))
+        })
+        .collect()
+}
+
+/// Returns the Name line of a status file. Unlike comm in the stat file it can't be
+/// confused by spaces or parentheses in the name
+pub(crate) fn status_name(status: &str) -> String {
+    for line in status.lines() {
+        if let Some(name) = line.strip_prefix("Name:") {
+            return name.trim_start_matches('\t').to_string();
+        }
+    }
+    String::new()
+}
+
+/// Returns a field of a stat file, numbered from 1 as in proc(5). Fields are counted
+/// from the last ')' so a comm containing spaces or parentheses doesn't shift them
+pub(crate) fn stat_field(stat: &str, field: usize) -> Option<&str> {
+    if field < 3 {
+        return None;
+    }
+    let rest = &stat[stat.rfind(')')? + 1..];
+    rest.split_whitespace().nth(field - 3)
+}
+
+/// Returns the kB value of a key in a status file, kernel threads have no Vm* lines so
+/// missing keys are 0
+pub(crate) fn status_kb(status: &str, key: &str) -> u64 {
+    for line in status.lines() {
+        let mut split = line.split_whitespace();
+        if split.next() == Some(&format!("{}:", key)[..]) {
+            return split.next().map_or(0, |v| v.parse::<u64>().unwrap_or(0));
+        }
+    }
+    0
+}
+
 #[derive(Debug)]
 enum ProcessState {
     Running,
@@ -247,6 +388,11 @@ impl Process {
         self.state.to_string()
     }
 
+    /// CPU the process last ran on, cpu::Cpus::get gives its core, package and node
+    pub fn get_processor(&self) -> i64 {
+        self.processor
+    }
+
     pub fn cmdline(p: i64) -> String {
         let path = format!("/proc/{}/cmdline", p);
         match fs::File::open(path) {
@@ -278,116 +424,67 @@ impl Process {
         }
     }
 
+    /// comm is wrapped in parentheses and may itself contain spaces and ')', so the fields
+    /// after it are counted from the last ')'
     fn parse(s: String) -> Process {
-        let store: Vec<&str> = s.split_whitespace().collect();
-        if store[1].starts_with("(") && store[2].ends_with(")") {
-            Process {
-                pid: store[0].parse::<i64>().unwrap(),
-                comm: format!("{} {}", store[1], store[2]),
-                state: ProcessState::from_str(store[3]).unwrap(),
-                ppid: store[4].parse::<i64>().unwrap(),
-                pgrp: store[5].parse::<i64>().unwrap(),
-                session: store[6].parse::<i64>().unwrap(),
-                tty_nr: store[7].parse::<i64>().unwrap(),
-                tpgid: store[8].parse::<i64>().unwrap(),
-                flags: store[9].parse::<i64>().unwrap(),
-                minflt: store[10].parse::<i64>().unwrap(),
-                cminflt: store[11].parse::<i64>().unwrap(),
-                majflt: store[12].parse::<i64>().unwrap(),
-                cmajflt: store[13].parse::<i64>().unwrap(),
-                utime: store[14].parse::<i64>().unwrap(),
-                stime: store[15].parse::<i64>().unwrap(),
-                cutime: store[16].parse::<i64>().unwrap(),
-                cstime: store[17].parse::<i64>().unwrap(),
-                priority: store[18].parse::<i64>().unwrap(),
-                nice: store[19].parse::<i64>().unwrap(),
-                num_threads: store[20].parse::<i64>().unwrap(),
-                itrealvalue: store[21].parse::<i64>().unwrap(),
-                starttime: store[22].parse::<i128>().unwrap(),
-                vsize: store[23].parse::<i64>().unwrap(),
-                rss: store[24].parse::<i64>().unwrap(),
-                rsslim: store[25].parse::<i128>().unwrap(),
-                startcode: store[26].parse::<i64>().unwrap(),
-                endcode: store[27].parse::<i64>().unwrap(),
-                startstack: store[28].parse::<i64>().unwrap(),
-                kstkesp: store[29].parse::<i64>().unwrap(),
-                signal: store[30].parse::<i64>().unwrap(),
-                blocked: store[31].parse::<i64>().unwrap(),
-                sigignore: store[32].parse::<i64>().unwrap(),
-                sigcatch: store[33].parse::<i64>().unwrap(),
-                wchan: store[34].parse::<i64>().unwrap(),
-                nswap: store[35].parse::<i64>().unwrap(),
-                cnswap: store[36].parse::<i64>().unwrap(),
-                exit_signal: store[37].parse::<i64>().unwrap(),
-                processor: store[38].parse::<i64>().unwrap(),
-                rt_priority: store[39].parse::<i64>().unwrap(),
-                policy: store[40].parse::<i64>().unwrap(),
-                delayacct_blkio_ticks: store[41].parse::<i128>().unwrap(),
-                guest_time: store[42].parse::<i64>().unwrap(),
-                cguest_time: store[43].parse::<i64>().unwrap(),
-                start_data: store[44].parse::<i64>().unwrap(),
-                end_data: store[45].parse::<i64>().unwrap(),
-                start_brk: store[46].parse::<i64>().unwrap(),
-                arg_start: store[47].parse::<i64>().unwrap(),
-                arg_end: store[48].parse::<i64>().unwrap(),
-                env_start: store[49].parse::<i64>().unwrap(),
-                env_end: store[50].parse::<i64>().unwrap(),
-                exit_code: store[51].parse::<i64>().unwrap(),
-            }
-        } else {
-            Process {
-                pid: store[0].parse::<i64>().unwrap(),
-                comm: store[1].to_string(),
-                state: ProcessState::from_str(store[2]).unwrap(),
-                ppid: store[3].parse::<i64>().unwrap(),
-                pgrp: store[4].parse::<i64>().unwrap(),
-                session: store[5].parse::<i64>().unwrap(),
-                tty_nr: store[6].parse::<i64>().unwrap(),
-                tpgid: store[7].parse::<i64>().unwrap(),
-                flags: store[8].parse::<i64>().unwrap(),
-                minflt: store[9].parse::<i64>().unwrap(),
-                cminflt: store[10].parse::<i64>().unwrap(),
-                majflt: store[11].parse::<i64>().unwrap(),
-                cmajflt: store[12].parse::<i64>().unwrap(),
-                utime: store[13].parse::<i64>().unwrap(),
-                stime: store[14].parse::<i64>().unwrap(),
-                cutime: store[15].parse::<i64>().unwrap(),
-                cstime: store[16].parse::<i64>().unwrap(),
-                priority: store[17].parse::<i64>().unwrap(),
-                nice: store[18].parse::<i64>().unwrap(),
-                num_threads: store[19].parse::<i64>().unwrap(),
-                itrealvalue: store[20].parse::<i64>().unwrap(),
-                starttime: store[21].parse::<i128>().unwrap(),
-                vsize: store[22].parse::<i64>().unwrap(),
-                rss: store[23].parse::<i64>().unwrap(),
-                rsslim: store[24].parse::<i128>().unwrap(),
-                startcode: store[25].parse::<i64>().unwrap(),
-                endcode: store[26].parse::<i64>().unwrap(),
-                startstack: store[27].parse::<i64>().unwrap(),
-                kstkesp: store[28].parse::<i64>().unwrap(),
-                signal: store[29].parse::<i64>().unwrap(),
-                blocked: store[30].parse::<i64>().unwrap(),
-                sigignore: store[31].parse::<i64>().unwrap(),
-                sigcatch: store[32].parse::<i64>().unwrap(),
-                wchan: store[33].parse::<i64>().unwrap(),
-                nswap: store[34].parse::<i64>().unwrap(),
-                cnswap: store[35].parse::<i64>().unwrap(),
-                exit_signal: store[36].parse::<i64>().unwrap(),
-                processor: store[37].parse::<i64>().unwrap(),
-                rt_priority: store[38].parse::<i64>().unwrap(),
-                policy: store[39].parse::<i64>().unwrap(),
-                delayacct_blkio_ticks: store[40].parse::<i128>().unwrap(),
-                guest_time: store[41].parse::<i64>().unwrap(),
-                cguest_time: store[42].parse::<i64>().unwrap(),
-                start_data: store[43].parse::<i64>().unwrap(),
-                end_data: store[44].parse::<i64>().unwrap(),
-                start_brk: store[44].parse::<i64>().unwrap(),
-                arg_start: store[45].parse::<i64>().unwrap(),
-                arg_end: store[46].parse::<i64>().unwrap(),
-                env_start: store[47].parse::<i64>().unwrap(),
-                env_end: store[48].parse::<i64>().unwrap(),
-                exit_code: store[49].parse::<i64>().unwrap(),
-            }
+        let open = s.find('(').unwrap_or(0);
+        let close = s.rfind(')').map_or(open, |c| c + 1);
+        let mut store: Vec<&str> = vec![s[..open].trim(), &s[open..close]];
+        store.extend(s[close..].split_whitespace());
+        // start_data onwards were added in 3.3 and exit_code in 3.5
+        let late = |i: usize| store.get(i).map_or(0, |v| v.parse::<i64>().unwrap());
+        Process {
+            pid: store[0].parse::<i64>().unwrap(),
+            comm: store[1].to_string(),
+            state: ProcessState::from_str(store[2]).unwrap(),
+            ppid: store[3].parse::<i64>().unwrap(),
+            pgrp: store[4].parse::<i64>().unwrap(),
+            session: store[5].parse::<i64>().unwrap(),
+            tty_nr: store[6].parse::<i64>().unwrap(),
+            tpgid: store[7].parse::<i64>().unwrap(),
+            flags: store[8].parse::<i64>().unwrap(),
+            minflt: store[9].parse::<i64>().unwrap(),
+            cminflt: store[10].parse::<i64>().unwrap(),
+            majflt: store[11].parse::<i64>().unwrap(),
+            cmajflt: store[12].parse::<i64>().unwrap(),
+            utime: store[13].parse::<i64>().unwrap(),
+            stime: store[14].parse::<i64>().unwrap(),
+            cutime: store[15].parse::<i64>().unwrap(),
+            cstime: store[16].parse::<i64>().unwrap(),
+            priority: store[17].parse::<i64>().unwrap(),
+            nice: store[18].parse::<i64>().unwrap(),
+            num_threads: store[19].parse::<i64>().unwrap(),
+            itrealvalue: store[20].parse::<i64>().unwrap(),
+            starttime: store[21].parse::<i128>().unwrap(),
+            vsize: store[22].parse::<i64>().unwrap(),
+            rss: store[23].parse::<i64>().unwrap(),
+            rsslim: store[24].parse::<i128>().unwrap(),
+            startcode: store[25].parse::<i64>().unwrap(),
+            endcode: store[26].parse::<i64>().unwrap(),
+            startstack: store[27].parse::<i64>().unwrap(),
+            kstkesp: store[28].parse::<i64>().unwrap(),
+            signal: store[30].parse::<i64>().unwrap(),
+            blocked: store[31].parse::<i64>().unwrap(),
+            sigignore: store[32].parse::<i64>().unwrap(),
+            sigcatch: store[33].parse::<i64>().unwrap(),
+            wchan: store[34].parse::<i64>().unwrap(),
+            nswap: store[35].parse::<i64>().unwrap(),
+            cnswap: store[36].parse::<i64>().unwrap(),
+            exit_signal: store[37].parse::<i64>().unwrap(),
+            processor: store[38].parse::<i64>().unwrap(),
+            rt_priority: store[39].parse::<i64>().unwrap(),
+            policy: store[40].parse::<i64>().unwrap(),
+            delayacct_blkio_ticks: store[41].parse::<i128>().unwrap(),
+            guest_time: store[42].parse::<i64>().unwrap(),
+            cguest_time: store[43].parse::<i64>().unwrap(),
+            start_data: late(44),
+            end_data: late(45),
+            start_brk: late(46),
+            arg_start: late(47),
+            arg_end: late(48),
+            env_start: late(49),
+            env_end: late(50),
+            exit_code: late(51),
         }
     }
 }
@@ -464,18 +561,18 @@ fn test_process_parse_412_kernel() {
         startstack: "0".parse::<i64>().unwrap(),
         kstkesp: "0".parse::<i64>().unwrap(),
         signal: "0".parse::<i64>().unwrap(),
-        blocked: "0".parse::<i64>().unwrap(),
-        sigignore: "671173123".parse::<i64>().unwrap(),
-        sigcatch: "4096".parse::<i64>().unwrap(),
-        wchan: "1260".parse::<i64>().unwrap(),
+        blocked: "671173123".parse::<i64>().unwrap(),
+        sigignore: "4096".parse::<i64>().unwrap(),
+        sigcatch: "1260".parse::<i64>().unwrap(),
+        wchan: "0".parse::<i64>().unwrap(),
         nswap: "0".parse::<i64>().unwrap(),
         cnswap: "0".parse::<i64>().unwrap(),
-        exit_signal: "0".parse::<i64>().unwrap(),
-        processor: "17".parse::<i64>().unwrap(),
+        exit_signal: "17".parse::<i64>().unwrap(),
+        processor: "0".parse::<i64>().unwrap(),
         rt_priority: "0".parse::<i64>().unwrap(),
         policy: "0".parse::<i64>().unwrap(),
-        delayacct_blkio_ticks: "0".parse::<i128>().unwrap(),
-        guest_time: "65755103".parse::<i64>().unwrap(),
+        delayacct_blkio_ticks: "65755103".parse::<i128>().unwrap(),
+        guest_time: "0".parse::<i64>().unwrap(),
         cguest_time: "0".parse::<i64>().unwrap(),
         start_data: "0".parse::<i64>().unwrap(),
         end_data: "0".parse::<i64>().unwrap(),
@@ -524,18 +621,18 @@ fn test_process_parse_48_kernel() {
         startstack: "0".parse::<i64>().unwrap(),
         kstkesp: "0".parse::<i64>().unwrap(),
         signal: "0".parse::<i64>().unwrap(),
-        blocked: "0".parse::<i64>().unwrap(),
-        sigignore: "671173123".parse::<i64>().unwrap(),
-        sigcatch: "4096".parse::<i64>().unwrap(),
-        wchan: "1260".parse::<i64>().unwrap(),
+        blocked: "671173123".parse::<i64>().unwrap(),
+        sigignore: "4096".parse::<i64>().unwrap(),
+        sigcatch: "1260".parse::<i64>().unwrap(),
+        wchan: "0".parse::<i64>().unwrap(),
         nswap: "0".parse::<i64>().unwrap(),
         cnswap: "0".parse::<i64>().unwrap(),
-        exit_signal: "0".parse::<i64>().unwrap(),
-        processor: "17".parse::<i64>().unwrap(),
-        rt_priority: "1".parse::<i64>().unwrap(),
+        exit_signal: "17".parse::<i64>().unwrap(),
+        processor: "1".parse::<i64>().unwrap(),
+        rt_priority: "0".parse::<i64>().unwrap(),
         policy: "0".parse::<i64>().unwrap(),
-        delayacct_blkio_ticks: "0".parse::<i128>().unwrap(),
-        guest_time: "7".parse::<i64>().unwrap(),
+        delayacct_blkio_ticks: "7".parse::<i128>().unwrap(),
+        guest_time: "0".parse::<i64>().unwrap(),
         cguest_time: "0".parse::<i64>().unwrap(),
         start_data: "0".parse::<i64>().unwrap(),
         end_data: "0".parse::<i64>().unwrap(),
@@ -585,17 +682,17 @@ fn test_process_parse_44_kernel() {
         kstkesp: "0".parse::<i64>().unwrap(),
         signal: "0".parse::<i64>().unwrap(),
         blocked: "0".parse::<i64>().unwrap(),
-        sigignore: "0".parse::<i64>().unwrap(),
-        sigcatch: "4096".parse::<i64>().unwrap(),
-        wchan: "536962595".parse::<i64>().unwrap(),
+        sigignore: "4096".parse::<i64>().unwrap(),
+        sigcatch: "536962595".parse::<i64>().unwrap(),
+        wchan: "0".parse::<i64>().unwrap(),
         nswap: "0".parse::<i64>().unwrap(),
         cnswap: "0".parse::<i64>().unwrap(),
-        exit_signal: "0".parse::<i64>().unwrap(),
-        processor: "17".parse::<i64>().unwrap(),
-        rt_priority: "1".parse::<i64>().unwrap(),
+        exit_signal: "17".parse::<i64>().unwrap(),
+        processor: "1".parse::<i64>().unwrap(),
+        rt_priority: "0".parse::<i64>().unwrap(),
         policy: "0".parse::<i64>().unwrap(),
-        delayacct_blkio_ticks: "0".parse::<i128>().unwrap(),
-        guest_time: "1".parse::<i64>().unwrap(),
+        delayacct_blkio_ticks: "1".parse::<i128>().unwrap(),
+        guest_time: "0".parse::<i64>().unwrap(),
         cguest_time: "0".parse::<i64>().unwrap(),
         start_data: "0".parse::<i64>().unwrap(),
         end_data: "0".parse::<i64>().unwrap(),
@@ -615,6 +712,18 @@ fn test_is_alive() {
     assert_eq!(t, true)
 }
 
+#[test]
+fn test_process_parse_odd_comm() {
+    let stat = "4242 (Web Content (x) y)) R 1 4242 4242 0 -1 4194560 0 0 0 0 7 3 0 0 20 0 1 0 \
+                5 0 0 18446744073709551615 0 0 0 0 0 0 0 0 0 0 0 0 17 6 0 0 0 0 0 0 0 0 0 0 0 0";
+    let p = Process::parse(stat.to_string());
+    assert_eq!(p.get_pid(), 4242);
+    assert_eq!(p.comm, "(Web Content (x) y))");
+    assert_eq!(p.stime, 3);
+    assert_eq!(p.exit_signal, 17);
+    assert_eq!(p.get_processor(), 6)
+}
+
 #[test]
 #[should_panic]
 fn test_process_not_found_panic() {
@@ -636,3 +745,23 @@ fn parse_statm() {
     };
     assert_eq!(t, pm)
 }
+
+#[test]
+fn parse_oom() {
+    let status = "Name:\tpostgres\nVmPeak:\t  231012 kB\nVmRSS:\t   51200 kB\nVmSwap:\t    1024 kB\n";
+    let t = ProcessOom::parse("683\n", "200\n", "3\n", status);
+    let oom = ProcessOom {
+        oom_score: 683,
+        oom_score_adj: 200,
+        oom_adj: 3,
+        vm_rss: 51200,
+        vm_swap: 1024,
+    };
+    assert_eq!(t, oom);
+    assert!(t.is_killable());
+    assert_eq!(status_name("Name:\tIsolated Web Co\nUmask:\t0022\n"), "Isolated Web Co");
+    let stat = "42 (Web Content (x)) S 1 42 42 0 -1 4194560 0 0 0 0 0 0 0 0 20 0 1 0 5 \
+                0 0 18446744073709551615 0 0 0 0 0 0 0 0 0 0 0 0 17 3 0 0 0 0 0";
+    assert_eq!(stat_field(stat, 3), Some("S"));
+    assert_eq!(stat_field(stat, 39), Some("3"))
+}
@@ -0,0 +1,323 @@
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use libc;
+use oom::CGROUP;
+
+pub static PRESSURE: &str = "/proc/pressure";
+
+/// The resources PSI tracks, irq needs Linux 6.1 and CONFIG_IRQ_TIME_ACCOUNTING
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Resource {
+    Cpu,
+    Memory,
+    Io,
+    Irq,
+}
+
+impl Resource {
+    /// Name of the file under /proc/pressure, cgroups add a .pressure suffix
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Resource::Cpu => "cpu",
+            Resource::Memory => "memory",
+            Resource::Io => "io",
+            Resource::Irq => "irq",
+        }
+    }
+}
+
+/// some: at least one task stalled on the resource, full: every non-idle task stalled
+/// at the same time
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum StallType {
+    Some,
+    Full,
+}
+
+impl StallType {
+    fn name(&self) -> &'static str {
+        match *self {
+            StallType::Some => "some",
+            StallType::Full => "full",
+        }
+    }
+}
+
+/// One line of a pressure file, averages are percentages of wall time
+#[derive(Debug)]
+pub struct PressureLine {
+    avg10: f64,
+    avg60: f64,
+    avg300: f64,
+    total: u64,
+}
+
+impl PartialEq for PressureLine {
+    fn eq(&self, other: &PressureLine) -> bool {
+        (self.avg10 == other.avg10) & (self.avg60 == other.avg60)
+            & (self.avg300 == other.avg300) & (self.total == other.total)
+    }
+}
+
+impl fmt::Display for PressureLine {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "avg10={:.2} avg60={:.2} avg300={:.2} total={}",
+            self.avg10, self.avg60, self.avg300, self.total
+        )
+    }
+}
+
+impl PressureLine {
+    pub fn get_avg10(&self) -> f64 {
+        self.avg10
+    }
+
+    pub fn get_avg60(&self) -> f64 {
+        self.avg60
+    }
+
+    pub fn get_avg300(&self) -> f64 {
+        self.avg300
+    }
+
+    /// Total stall time in microseconds
+    pub fn get_total(&self) -> u64 {
+        self.total
+    }
+
+    /// Percentage of the time stalled between an earlier sample and this one taken
+    /// `secs` apart, for intervals the fixed averages don't cover
+    pub fn stall_percent(&self, prev: &PressureLine, secs: f64) -> f64 {
+        if secs <= 0.0 {
+            return 0.0;
+        }
+        let stalled = self.total.saturating_sub(prev.total) as f64 / 1_000_000.0;
+        (stalled / secs * 100.0).min(100.0)
+    }
+
+    fn parse(fields: &[&str]) -> PressureLine {
+        let mut line = PressureLine {
+            avg10: 0.0,
+            avg60: 0.0,
+            avg300: 0.0,
+            total: 0,
+        };
+        for field in fields {
+            let (key, value) = match field.find('=') {
+                Some(i) => (&field[..i], &field[i + 1..]),
+                None => continue,
+            };
+            match key {
+                "avg10" => line.avg10 = value.parse::<f64>().unwrap_or(0.0),
+                "avg60" => line.avg60 = value.parse::<f64>().unwrap_or(0.0),
+                "avg300" => line.avg300 = value.parse::<f64>().unwrap_or(0.0),
+                "total" => line.total = value.parse::<u64>().unwrap_or(0),
+                _ => (),
+            }
+        }
+        line
+    }
+}
+
+/// Pressure on one resource, system wide or for a cgroup
+#[derive(Debug)]
+pub struct Pressure {
+    some: Option<PressureLine>,
+    full: Option<PressureLine>,
+}
+
+impl PartialEq for Pressure {
+    fn eq(&self, other: &Pressure) -> bool {
+        (self.some == other.some) & (self.full == other.full)
+    }
+}
+
+impl fmt::Display for Pressure {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref some) = self.some {
+            write!(fmtr, "some {}", some)?;
+        }
+        if let Some(ref full) = self.full {
+            if self.some.is_some() {
+                write!(fmtr, " ")?;
+            }
+            write!(fmtr, "full {}", full)?;
+        }
+        Ok(())
+    }
+}
+
+impl Pressure {
+    /// None for irq, which only has a full line
+    pub fn get_some(&self) -> Option<&PressureLine> {
+        self.some.as_ref()
+    }
+
+    /// None on kernels before 5.13 for cpu, which only reported some
+    pub fn get_full(&self) -> Option<&PressureLine> {
+        self.full.as_ref()
+    }
+
+    pub fn get(&self, stall: StallType) -> Option<&PressureLine> {
+        match stall {
+            StallType::Some => self.some.as_ref(),
+            StallType::Full => self.full.as_ref(),
+        }
+    }
+
+    /// System wide pressure, fails when the kernel has no PSI support or was booted
+    /// with psi=0
+    pub fn system(resource: Resource) -> Result<Pressure, io::Error> {
+        let s = Pressure::fetch(&system_path(resource))?;
+        Ok(Pressure::parse(&s))
+    }
+
+    /// Pressure for a cgroup v2 path relative to the cgroup root, e.g. "system.slice"
+    pub fn cgroup(path: &str, resource: Resource) -> Result<Pressure, io::Error> {
+        let s = Pressure::fetch(&cgroup_path(path, resource))?;
+        Ok(Pressure::parse(&s))
+    }
+
+    fn fetch(path: &str) -> Result<String, io::Error> {
+        let mut s = String::new();
+        let mut f = File::open(path)?;
+        f.read_to_string(&mut s)?;
+        Ok(s)
+    }
+
+    fn parse(s: &str) -> Pressure {
+        let mut pressure = Pressure {
+            some: None,
+            full: None,
+        };
+        for line in s.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.first() {
+                Some(&"some") => pressure.some = Some(PressureLine::parse(&fields[1..])),
+                Some(&"full") => pressure.full = Some(PressureLine::parse(&fields[1..])),
+                _ => (),
+            }
+        }
+        pressure
+    }
+}
+
+/// A PSI trigger, the kernel signals it when the stall time within a window goes over a
+/// threshold. The trigger is removed when this is dropped
+#[derive(Debug)]
+pub struct PressureTrigger {
+    file: File,
+}
+
+impl PressureTrigger {
+    /// Watch system wide pressure. Thresholds and windows are in microseconds, windows
+    /// must be between 500ms and 10s. Without CAP_SYS_RESOURCE the window has to be a
+    /// multiple of 2s
+    pub fn system(
+        resource: Resource,
+        stall: StallType,
+        threshold_us: u64,
+        window_us: u64,
+    ) -> Result<PressureTrigger, io::Error> {
+        PressureTrigger::open(&system_path(resource), stall, threshold_us, window_us)
+    }
+
+    /// Watch pressure in a cgroup v2 path relative to the cgroup root
+    pub fn cgroup(
+        path: &str,
+        resource: Resource,
+        stall: StallType,
+        threshold_us: u64,
+        window_us: u64,
+    ) -> Result<PressureTrigger, io::Error> {
+        PressureTrigger::open(&cgroup_path(path, resource), stall, threshold_us, window_us)
+    }
+
+    /// Block until the trigger fires, or for timeout_ms milliseconds with -1 waiting
+    /// forever. Returns false on timeout. Fails if the cgroup being watched is removed
+    pub fn wait(&self, timeout_ms: i32) -> Result<bool, io::Error> {
+        let mut fds = libc::pollfd {
+            fd: self.file.as_raw_fd(),
+            events: libc::POLLPRI,
+            revents: 0,
+        };
+        loop {
+            let r = unsafe { libc::poll(&mut fds, 1, timeout_ms) };
+            if r < 0 {
+                let e = io::Error::last_os_error();
+                if e.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(e);
+            }
+            if fds.revents & libc::POLLERR != 0 {
+                return Err(io::Error::other("Pressure file went away"));
+            }
+            return Ok(r > 0);
+        }
+    }
+
+    fn open(
+        path: &str,
+        stall: StallType,
+        threshold_us: u64,
+        window_us: u64,
+    ) -> Result<PressureTrigger, io::Error> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(path)?;
+        file.write_all(trigger(stall, threshold_us, window_us).as_bytes())?;
+        Ok(PressureTrigger { file })
+    }
+}
+
+/// The kernel wants the whole trigger in one write, terminated with a nul
+fn trigger(stall: StallType, threshold_us: u64, window_us: u64) -> String {
+    format!("{} {} {}\0", stall.name(), threshold_us, window_us)
+}
+
+fn system_path(resource: Resource) -> String {
+    format!("{}/{}", PRESSURE, resource.name())
+}
+
+fn cgroup_path(path: &str, resource: Resource) -> String {
+    format!("{}/{}/{}.pressure", CGROUP, path, resource.name())
+}
+
+#[test]
+fn test_pressure_parse() {
+    let memory = "some avg10=1.53 avg60=0.87 avg300=0.22 total=4187653
+full avg10=0.00 avg60=0.12 avg300=0.05 total=1254031
+";
+    let p = Pressure::parse(memory);
+    assert_eq!(
+        p.get_some(),
+        Some(&PressureLine {
+            avg10: 1.53,
+            avg60: 0.87,
+            avg300: 0.22,
+            total: 4187653,
+        })
+    );
+    assert_eq!(p.get(StallType::Full).unwrap().get_avg60(), 0.12);
+
+    let irq = Pressure::parse("full avg10=0.00 avg60=0.00 avg300=0.00 total=13041\n");
+    assert_eq!(irq.get_some(), None);
+    let later = Pressure::parse("full avg10=0.00 avg60=0.00 avg300=0.00 total=513041\n");
+    let pct = later.get_full().unwrap().stall_percent(irq.get_full().unwrap(), 10.0);
+    assert_eq!(pct, 5.0);
+
+    assert_eq!(trigger(StallType::Some, 150000, 1000000), "some 150000 1000000\0");
+    assert_eq!(
+        cgroup_path("system.slice", Resource::Io),
+        "/sys/fs/cgroup/system.slice/io.pressure"
+    );
+}
@@ -0,0 +1,325 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use libc;
+use sysfs::read_first_num;
+
+pub static SLABINFO: &str = "/proc/slabinfo";
+pub static SYS_SLAB: &str = "/sys/kernel/slab";
+
+/// One kmem cache
+#[derive(Debug)]
+pub struct SlabCache {
+    name: String,
+    active_objs: u64,
+    num_objs: u64,
+    obj_size: u64,
+    objs_per_slab: u64,
+    pages_per_slab: u64,
+    num_slabs: u64,
+}
+
+impl PartialEq for SlabCache {
+    fn eq(&self, other: &SlabCache) -> bool {
+        (self.name == other.name) & (self.active_objs == other.active_objs)
+            & (self.num_objs == other.num_objs) & (self.obj_size == other.obj_size)
+            & (self.objs_per_slab == other.objs_per_slab)
+            & (self.pages_per_slab == other.pages_per_slab)
+            & (self.num_slabs == other.num_slabs)
+    }
+}
+
+impl fmt::Display for SlabCache {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "{} {}/{} objects of {} bytes, {} bytes",
+            self.name,
+            self.active_objs,
+            self.num_objs,
+            self.obj_size,
+            self.memory()
+        )
+    }
+}
+
+impl SlabCache {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_active_objs(&self) -> u64 {
+        self.active_objs
+    }
+
+    pub fn get_num_objs(&self) -> u64 {
+        self.num_objs
+    }
+
+    pub fn get_obj_size(&self) -> u64 {
+        self.obj_size
+    }
+
+    pub fn get_objs_per_slab(&self) -> u64 {
+        self.objs_per_slab
+    }
+
+    pub fn get_pages_per_slab(&self) -> u64 {
+        self.pages_per_slab
+    }
+
+    pub fn get_num_slabs(&self) -> u64 {
+        self.num_slabs
+    }
+
+    /// Bytes held by the cache's slabs, including free objects and padding
+    pub fn memory(&self) -> u64 {
+        self.num_slabs * self.pages_per_slab * page_size()
+    }
+
+    /// Percentage of the allocated objects in use
+    pub fn active_percent(&self) -> f64 {
+        if self.num_objs == 0 {
+            return 0.0;
+        }
+        self.active_objs as f64 / self.num_objs as f64 * 100.0
+    }
+
+    /// A line of slabinfo version 2.1, the tunables and slabdata sections follow ":"
+    fn parse(line: &str) -> Option<SlabCache> {
+        let sections: Vec<Vec<&str>> = line
+            .split(':')
+            .map(|s| s.split_whitespace().collect())
+            .collect();
+        let head = sections.first()?;
+        let slabdata = sections.iter().find(|s| s.first() == Some(&"slabdata"))?;
+        if (head.len() < 6) | (slabdata.len() < 3) {
+            return None;
+        }
+        let num = |s: &str| s.parse::<u64>().unwrap_or(0);
+        Some(SlabCache {
+            name: head[0].to_string(),
+            active_objs: num(head[1]),
+            num_objs: num(head[2]),
+            obj_size: num(head[3]),
+            objs_per_slab: num(head[4]),
+            pages_per_slab: num(head[5]),
+            num_slabs: num(slabdata[2]),
+        })
+    }
+
+    /// A SLUB cache directory. objects and total_objects need CONFIG_SLUB_DEBUG so
+    /// missing attributes are 0, but the counts are mode 0400 and an attribute that can't
+    /// be read is an error rather than an empty cache
+    fn read(dir: &Path, name: &str) -> Result<SlabCache, io::Error> {
+        let num = |attr: &str| match read_first_num(dir, attr) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(0),
+            result => result,
+        };
+        Ok(SlabCache {
+            name: name.to_string(),
+            active_objs: num("objects")?,
+            num_objs: num("total_objects")?,
+            obj_size: num("object_size")?,
+            objs_per_slab: num("objs_per_slab")?,
+            pages_per_slab: 1 << num("order")?,
+            num_slabs: num("slabs")?,
+        })
+    }
+}
+
+/// Change in a cache between two samples, negative when it shrank
+#[derive(Debug, PartialEq)]
+pub struct SlabGrowth {
+    name: String,
+    objects: i64,
+    memory: i64,
+}
+
+impl fmt::Display for SlabGrowth {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmtr, "{} {:+} objects {:+} bytes", self.name, self.objects, self.memory)
+    }
+}
+
+impl SlabGrowth {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_objects(&self) -> i64 {
+        self.objects
+    }
+
+    pub fn get_memory(&self) -> i64 {
+        self.memory
+    }
+}
+
+/// Slab allocator caches, the breakdown behind Slab, SReclaimable and SUnreclaim in
+/// /proc/meminfo
+#[derive(Debug)]
+pub struct SlabInfo {
+    caches: Vec<SlabCache>,
+}
+
+impl SlabInfo {
+    pub fn get_caches(self) -> Vec<SlabCache> {
+        self.caches
+    }
+
+    /// Reads /proc/slabinfo, which is only readable by root, falling back to
+    /// /sys/kernel/slab. Fails with PermissionDenied when neither can be read
+    pub fn new() -> Result<SlabInfo, io::Error> {
+        match SlabInfo::proc() {
+            Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                match SlabInfo::sys(Path::new(SYS_SLAB)) {
+                    Ok(info) if !info.caches.is_empty() => Ok(info),
+                    _ => Err(io::Error::new(
+                        io::ErrorKind::PermissionDenied,
+                        "/proc/slabinfo needs root and /sys/kernel/slab is unavailable",
+                    )),
+                }
+            }
+            result => result,
+        }
+    }
+
+    pub fn proc() -> Result<SlabInfo, io::Error> {
+        Ok(SlabInfo::parse(&fs::read_to_string(SLABINFO)?))
+    }
+
+    /// SLUB's sysfs tree, caches merged with others show up as symlinks to a shared
+    /// directory and are only counted once, under the first alias. Caches destroyed while
+    /// the tree is walked are skipped, unreadable counts fail with PermissionDenied
+    pub fn sys(root: &Path) -> Result<SlabInfo, io::Error> {
+        let mut caches = vec![];
+        let mut seen = HashSet::new();
+        let mut names: Vec<String> = fs::read_dir(root)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .filter(|name| !name.starts_with(':'))
+            .collect();
+        names.sort();
+        for name in names {
+            let dir = root.join(&name);
+            let target = match fs::canonicalize(&dir) {
+                Ok(target) => target,
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            if seen.insert(target) {
+                caches.push(SlabCache::read(&dir, &name)?);
+            }
+        }
+        Ok(SlabInfo { caches })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SlabCache> {
+        self.caches.iter().find(|c| c.name == name)
+    }
+
+    /// Bytes held by all caches, close to Slab in /proc/meminfo
+    pub fn total(&self) -> u64 {
+        self.caches.iter().map(|c| c.memory()).sum()
+    }
+
+    /// Caches by memory used, largest first
+    pub fn sorted_by_memory(&self) -> Vec<&SlabCache> {
+        let mut caches: Vec<&SlabCache> = self.caches.iter().collect();
+        caches.sort_by_key(|c| Reverse(c.memory()));
+        caches
+    }
+
+    /// Change in each cache since an earlier sample, fastest growing first. A cache
+    /// that keeps growing across samples, like dentry from negative lookups, is the
+    /// usual sign of a slab leak
+    pub fn growth(&self, prev: &SlabInfo) -> Vec<SlabGrowth> {
+        let before: HashMap<&str, &SlabCache> =
+            prev.caches.iter().map(|c| (c.name.as_str(), c)).collect();
+        let mut growth: Vec<SlabGrowth> = self
+            .caches
+            .iter()
+            .map(|c| {
+                let (objects, memory) = match before.get(c.name.as_str()) {
+                    Some(p) => (p.active_objs, p.memory()),
+                    None => (0, 0),
+                };
+                SlabGrowth {
+                    name: c.name.clone(),
+                    objects: c.active_objs as i64 - objects as i64,
+                    memory: c.memory() as i64 - memory as i64,
+                }
+            })
+            .collect();
+        growth.sort_by_key(|g| Reverse(g.memory));
+        growth
+    }
+
+    fn parse(s: &str) -> SlabInfo {
+        SlabInfo {
+            caches: s
+                .lines()
+                .filter(|l| !l.starts_with("slabinfo") & !l.starts_with('#'))
+                .filter_map(SlabCache::parse)
+                .collect(),
+        }
+    }
+}
+
+fn page_size() -> u64 {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as u64 }
+}
+
+#[test]
+fn test_slabinfo() {
+    use testtree::TestTree;
+
+    let before = SlabInfo::parse(
+        "slabinfo - version: 2.1
+# name            <active_objs> <num_objs> <objsize> <objperslab> <pagesperslab> : tunables <limit> <batchcount> <sharedfactor> : slabdata <active_slabs> <num_slabs> <sharedavail>
+dentry            123690 130011    192   21    1 : tunables    0    0    0 : slabdata   6191   6191      0
+kmalloc-1k          2048   2048   1024   32    8 : tunables    0    0    0 : slabdata     64     64      0
+",
+    );
+    let dentry = before.get("dentry").unwrap();
+    assert_eq!(dentry.get_num_objs(), 130011);
+    assert_eq!(dentry.memory(), 6191 * page_size());
+    assert_eq!(before.sorted_by_memory()[1].get_name(), "kmalloc-1k");
+    assert_eq!(before.total(), (6191 + 64 * 8) * page_size());
+
+    let tree = TestTree::new("slab");
+    tree.write_all(&[
+        ("dentry/objects", "210000 N0=210000\n"),
+        ("dentry/total_objects", "210021 N0=210021\n"),
+        ("dentry/object_size", "192\n"),
+        ("dentry/objs_per_slab", "21\n"),
+        ("dentry/order", "0\n"),
+        ("dentry/slabs", "10001 N0=10001\n"),
+        (":0001024/objects", "2048 N0=2048\n"),
+        (":0001024/object_size", "1024\n"),
+        (":0001024/order", "3\n"),
+        (":0001024/slabs", "64 N0=64\n"),
+    ]);
+    tree.symlink(":0001024", "kmalloc-1k");
+    tree.symlink(":0001024", "kmalloc-cg-1k");
+    // a cache destroyed during the walk leaves its alias pointing nowhere
+    tree.symlink(":0000064", "kmalloc-rcl-64");
+
+    let after = SlabInfo::sys(tree.path()).unwrap();
+    assert_eq!(after.caches.len(), 2);
+    assert_eq!(after.get("kmalloc-1k").unwrap().get_pages_per_slab(), 8);
+    let growth = after.growth(&before);
+    assert_eq!(
+        growth[0],
+        SlabGrowth {
+            name: "dentry".to_string(),
+            objects: 210000 - 123690,
+            memory: ((10001 - 6191) * page_size()) as i64,
+        }
+    );
+    assert_eq!(growth[1].get_memory(), 0);
+}
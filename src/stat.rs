@@ -0,0 +1,389 @@
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub static STAT: &str = "/proc/stat";
+
+/// Time spent by a CPU in each mode, in USER_HZ ticks
+#[derive(Debug)]
+pub struct CpuTime {
+    cpu: Option<usize>,
+    user: u64,
+    nice: u64,
+    system: u64,
+    idle: u64,
+    iowait: u64,
+    irq: u64,
+    softirq: u64,
+    steal: u64,
+    guest: u64,
+    guest_nice: u64,
+}
+
+impl PartialEq for CpuTime {
+    fn eq(&self, other: &CpuTime) -> bool {
+        (self.cpu == other.cpu) & (self.user == other.user) & (self.nice == other.nice)
+            & (self.system == other.system) & (self.idle == other.idle)
+            & (self.iowait == other.iowait) & (self.irq == other.irq)
+            & (self.softirq == other.softirq) & (self.steal == other.steal)
+            & (self.guest == other.guest) & (self.guest_nice == other.guest_nice)
+    }
+}
+
+impl CpuTime {
+    /// CPU number, None for the aggregate line
+    pub fn get_cpu(&self) -> Option<usize> {
+        self.cpu
+    }
+
+    pub fn get_user(&self) -> u64 {
+        self.user
+    }
+
+    pub fn get_nice(&self) -> u64 {
+        self.nice
+    }
+
+    pub fn get_system(&self) -> u64 {
+        self.system
+    }
+
+    pub fn get_idle(&self) -> u64 {
+        self.idle
+    }
+
+    pub fn get_iowait(&self) -> u64 {
+        self.iowait
+    }
+
+    pub fn get_irq(&self) -> u64 {
+        self.irq
+    }
+
+    pub fn get_softirq(&self) -> u64 {
+        self.softirq
+    }
+
+    pub fn get_steal(&self) -> u64 {
+        self.steal
+    }
+
+    pub fn get_guest(&self) -> u64 {
+        self.guest
+    }
+
+    pub fn get_guest_nice(&self) -> u64 {
+        self.guest_nice
+    }
+
+    /// Ticks across all modes, guest time is already counted in user and nice so it is
+    /// left out
+    pub fn total(&self) -> u64 {
+        self.user + self.nice + self.system + self.idle + self.iowait + self.irq + self.softirq
+            + self.steal
+    }
+
+    /// Percentage of time spent in each mode between an earlier sample and this one
+    pub fn usage(&self, prev: &CpuTime) -> CpuUsage {
+        let total = self.total().saturating_sub(prev.total());
+        let pct = |now: u64, then: u64| {
+            if total == 0 {
+                0.0
+            } else {
+                now.saturating_sub(then) as f64 / total as f64 * 100.0
+            }
+        };
+        CpuUsage {
+            cpu: self.cpu,
+            user: pct(self.user, prev.user),
+            nice: pct(self.nice, prev.nice),
+            system: pct(self.system, prev.system),
+            idle: pct(self.idle, prev.idle),
+            iowait: pct(self.iowait, prev.iowait),
+            irq: pct(self.irq, prev.irq),
+            softirq: pct(self.softirq, prev.softirq),
+            steal: pct(self.steal, prev.steal),
+        }
+    }
+
+    fn parse(line: &str) -> CpuTime {
+        let split: Vec<&str> = line.split_whitespace().collect();
+        let cpu = split[0].trim_start_matches("cpu").parse::<usize>().ok();
+        // fields were added over time: steal in 2.6.11, guest in 2.6.24, guest_nice in 2.6.33
+        let field = |i: usize| split.get(i).map_or(0, |v| v.parse::<u64>().unwrap());
+        CpuTime {
+            cpu,
+            user: field(1),
+            nice: field(2),
+            system: field(3),
+            idle: field(4),
+            iowait: field(5),
+            irq: field(6),
+            softirq: field(7),
+            steal: field(8),
+            guest: field(9),
+            guest_nice: field(10),
+        }
+    }
+}
+
+/// CPU utilization between two samples as percentages
+#[derive(Debug)]
+pub struct CpuUsage {
+    cpu: Option<usize>,
+    user: f64,
+    nice: f64,
+    system: f64,
+    idle: f64,
+    iowait: f64,
+    irq: f64,
+    softirq: f64,
+    steal: f64,
+}
+
+impl fmt::Display for CpuUsage {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self.cpu {
+            Some(c) => format!("cpu{}", c),
+            None => "cpu".to_string(),
+        };
+        write!(
+            fmtr,
+            "{}: {:.1}% user {:.1}% system {:.1}% iowait {:.1}% idle",
+            name, self.user, self.system, self.iowait, self.idle
+        )
+    }
+}
+
+impl CpuUsage {
+    pub fn get_cpu(&self) -> Option<usize> {
+        self.cpu
+    }
+
+    pub fn get_user(&self) -> f64 {
+        self.user
+    }
+
+    pub fn get_nice(&self) -> f64 {
+        self.nice
+    }
+
+    pub fn get_system(&self) -> f64 {
+        self.system
+    }
+
+    pub fn get_idle(&self) -> f64 {
+        self.idle
+    }
+
+    pub fn get_iowait(&self) -> f64 {
+        self.iowait
+    }
+
+    pub fn get_irq(&self) -> f64 {
+        self.irq
+    }
+
+    pub fn get_softirq(&self) -> f64 {
+        self.softirq
+    }
+
+    pub fn get_steal(&self) -> f64 {
+        self.steal
+    }
+
+    /// Percentage of time not spent idle or waiting on I/O
+    pub fn busy(&self) -> f64 {
+        100.0 - self.idle - self.iowait
+    }
+}
+
+#[derive(Debug)]
+pub struct Stat {
+    total: CpuTime,
+    cpus: Vec<CpuTime>,
+    intr: u64,
+    ctxt: u64,
+    btime: u64,
+    processes: u64,
+    procs_running: u64,
+    procs_blocked: u64,
+    softirq: u64,
+}
+
+impl PartialEq for Stat {
+    fn eq(&self, other: &Stat) -> bool {
+        (self.total == other.total) & (self.cpus == other.cpus) & (self.intr == other.intr)
+            & (self.ctxt == other.ctxt) & (self.btime == other.btime)
+            & (self.processes == other.processes)
+            & (self.procs_running == other.procs_running)
+            & (self.procs_blocked == other.procs_blocked) & (self.softirq == other.softirq)
+    }
+}
+
+impl Stat {
+    pub fn new() -> Stat {
+        let text = Stat::fetch();
+        Stat::parse(text)
+    }
+
+    /// Aggregate of every CPU
+    pub fn get_total(&self) -> &CpuTime {
+        &self.total
+    }
+
+    /// Per CPU times, offline CPUs are not listed
+    pub fn get_cpus(&self) -> &Vec<CpuTime> {
+        &self.cpus
+    }
+
+    /// Interrupts serviced since boot
+    pub fn get_intr(&self) -> u64 {
+        self.intr
+    }
+
+    /// Context switches since boot
+    pub fn get_ctxt(&self) -> u64 {
+        self.ctxt
+    }
+
+    /// Boot time in seconds since the epoch
+    pub fn get_btime(&self) -> u64 {
+        self.btime
+    }
+
+    /// Forks since boot
+    pub fn get_processes(&self) -> u64 {
+        self.processes
+    }
+
+    pub fn get_procs_running(&self) -> u64 {
+        self.procs_running
+    }
+
+    pub fn get_procs_blocked(&self) -> u64 {
+        self.procs_blocked
+    }
+
+    /// Softirqs serviced since boot
+    pub fn get_softirq(&self) -> u64 {
+        self.softirq
+    }
+
+    pub fn boot_time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.btime)
+    }
+
+    /// Utilization of each CPU since an earlier sample, CPUs missing from either sample
+    /// because they were offline are skipped
+    pub fn usage(&self, prev: &Stat) -> Vec<CpuUsage> {
+        let mut usage = vec![];
+        for cpu in &self.cpus {
+            if let Some(p) = prev.cpus.iter().find(|p| p.cpu == cpu.cpu) {
+                usage.push(cpu.usage(p))
+            }
+        }
+        usage
+    }
+
+    /// Utilization of the whole system since an earlier sample
+    pub fn total_usage(&self, prev: &Stat) -> CpuUsage {
+        self.total.usage(&prev.total)
+    }
+
+    fn fetch() -> String {
+        let path = Path::new(STAT);
+        let mut data = String::new();
+        let mut f = File::open(path).expect("Unable to open file");
+        f.read_to_string(&mut data).expect("Unable to read string");
+        data
+    }
+
+    fn parse(st: String) -> Stat {
+        let mut stat = Stat {
+            total: CpuTime::parse("cpu"),
+            cpus: vec![],
+            intr: 0,
+            ctxt: 0,
+            btime: 0,
+            processes: 0,
+            procs_running: 0,
+            procs_blocked: 0,
+            softirq: 0,
+        };
+        for line in st.lines() {
+            let split: Vec<&str> = line.split_whitespace().collect();
+            if split.len() < 2 {
+                continue;
+            }
+            // intr and softirq are followed by per source counts, only the total is kept
+            let value = split[1].parse::<u64>().unwrap_or(0);
+            match split[0] {
+                "cpu" => stat.total = CpuTime::parse(line),
+                c if c.starts_with("cpu") => stat.cpus.push(CpuTime::parse(line)),
+                "intr" => stat.intr = value,
+                "ctxt" => stat.ctxt = value,
+                "btime" => stat.btime = value,
+                "processes" => stat.processes = value,
+                "procs_running" => stat.procs_running = value,
+                "procs_blocked" => stat.procs_blocked = value,
+                "softirq" => stat.softirq = value,
+                _ => {}
+            }
+        }
+        stat
+    }
+}
+
+#[test]
+fn test_stat_parse() {
+    let stat = "cpu  2255 34 2290 22625563 6290 127 456 0 0 0
+cpu0 1132 34 1441 11311718 3675 127 438 0 0 0
+cpu1 1123 0 849 11313845 2614 0 18 0 0 0
+intr 114930548 113199788 3 0 5 263 0 4 0 0 0
+ctxt 1990473
+btime 1062191376
+processes 2915
+procs_running 1
+procs_blocked 0
+softirq 183433 0 21755 12 39 1137 231 21459 2263
+";
+    let s = Stat::parse(stat.to_string());
+    assert_eq!(s.cpus.len(), 2);
+    assert_eq!(
+        s.cpus[1],
+        CpuTime {
+            cpu: Some(1),
+            user: 1123,
+            nice: 0,
+            system: 849,
+            idle: 11313845,
+            iowait: 2614,
+            irq: 0,
+            softirq: 18,
+            steal: 0,
+            guest: 0,
+            guest_nice: 0,
+        }
+    );
+    assert_eq!(s.intr, 114930548);
+    assert_eq!(s.ctxt, 1990473);
+    assert_eq!(s.btime, 1062191376);
+    assert_eq!(s.processes, 2915);
+    assert_eq!(s.softirq, 183433)
+}
+
+#[test]
+fn test_stat_usage() {
+    let before =
+        Stat::parse("cpu 100 0 100 800 0 0 0 0 0 0\ncpu0 100 0 100 800 0 0 0 0 0 0\n".to_string());
+    let after =
+        Stat::parse("cpu 150 0 150 900 0 0 0 0 0 0\ncpu0 150 0 150 900 0 0 0 0 0 0\n".to_string());
+    let usage = after.usage(&before);
+    assert_eq!(usage.len(), 1);
+    assert_eq!(usage[0].get_user(), 25.0);
+    assert_eq!(usage[0].get_idle(), 50.0);
+    assert_eq!(usage[0].busy(), 50.0)
+}
@@ -0,0 +1,285 @@
+use std::cmp::Reverse;
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use mounts::unescape;
+use process::{all_status, status_kb, status_name, PROC};
+use vmstat::VmStat;
+
+pub static SWAPS: &str = "/proc/swaps";
+
+/// A swap partition or swap file from /proc/swaps
+#[derive(Debug)]
+pub struct SwapDevice {
+    filename: PathBuf,
+    swap_type: String,
+    size: u64,
+    used: u64,
+    priority: i32,
+}
+
+impl PartialEq for SwapDevice {
+    fn eq(&self, other: &SwapDevice) -> bool {
+        (self.filename == other.filename) & (self.swap_type == other.swap_type)
+            & (self.size == other.size) & (self.used == other.used)
+            & (self.priority == other.priority)
+    }
+}
+
+impl fmt::Display for SwapDevice {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "Swap: {} Type: {} Size: {} Used: {} Priority: {}",
+            self.filename.display(),
+            self.swap_type,
+            self.size,
+            self.used,
+            self.priority
+        )
+    }
+}
+
+impl SwapDevice {
+    pub fn get_filename(&self) -> &Path {
+        &self.filename
+    }
+
+    /// "partition" or "file"
+    pub fn get_type(&self) -> &str {
+        &self.swap_type
+    }
+
+    /// Size in kB
+    pub fn get_size(&self) -> u64 {
+        self.size
+    }
+
+    /// Used in kB
+    pub fn get_used(&self) -> u64 {
+        self.used
+    }
+
+    /// Higher priority devices are filled first, devices of equal priority are used
+    /// round robin
+    pub fn get_priority(&self) -> i32 {
+        self.priority
+    }
+
+    pub fn used_percent(&self) -> f64 {
+        if self.size == 0 {
+            return 0.0;
+        }
+        self.used as f64 / self.size as f64 * 100.0
+    }
+}
+
+#[derive(Debug)]
+pub struct Swaps {
+    devices: Vec<SwapDevice>,
+}
+
+impl Swaps {
+    pub fn get_devices(self) -> Vec<SwapDevice> {
+        self.devices
+    }
+
+    pub fn new() -> Swaps {
+        let data = Swaps::fetch();
+        Swaps::parse(&data)
+    }
+
+    /// Total swap in kB across all devices
+    pub fn total(&self) -> u64 {
+        self.devices.iter().map(|d| d.size).sum()
+    }
+
+    /// Used swap in kB across all devices
+    pub fn used(&self) -> u64 {
+        self.devices.iter().map(|d| d.used).sum()
+    }
+
+    fn fetch() -> Vec<u8> {
+        let mut data = vec![];
+        let mut f = File::open(SWAPS).expect("Unable to open file");
+        f.read_to_end(&mut data).expect("Unable to read string");
+        data
+    }
+
+    /// Filenames are escaped the same way as mount points, so this works on bytes
+    fn parse(data: &[u8]) -> Swaps {
+        let mut devices = vec![];
+        // The first line is the column header
+        for line in data.split(|b| *b == b'\n').skip(1) {
+            let fields: Vec<&[u8]> = line.split(|b| *b == b' ' || *b == b'\t')
+                .filter(|f| !f.is_empty())
+                .collect();
+            if fields.len() < 5 {
+                continue;
+            }
+            let num = |f: &[u8]| String::from_utf8_lossy(f).parse::<u64>().unwrap_or(0);
+            devices.push(SwapDevice {
+                filename: unescape(fields[0]),
+                swap_type: String::from_utf8_lossy(fields[1]).into_owned(),
+                size: num(fields[2]),
+                used: num(fields[3]),
+                priority: String::from_utf8_lossy(fields[4]).parse::<i32>().unwrap_or(0),
+            })
+        }
+        Swaps { devices }
+    }
+}
+
+/// Pages swapped in and out since boot, from /proc/vmstat
+#[derive(Debug)]
+pub struct SwapActivity {
+    pswpin: u64,
+    pswpout: u64,
+}
+
+impl PartialEq for SwapActivity {
+    fn eq(&self, other: &SwapActivity) -> bool {
+        (self.pswpin == other.pswpin) & (self.pswpout == other.pswpout)
+    }
+}
+
+impl fmt::Display for SwapActivity {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmtr, "Swap in: {} Swap out: {}", self.pswpin, self.pswpout)
+    }
+}
+
+impl SwapActivity {
+    pub fn get_pswpin(&self) -> u64 {
+        self.pswpin
+    }
+
+    pub fn get_pswpout(&self) -> u64 {
+        self.pswpout
+    }
+
+    pub fn new() -> SwapActivity {
+        SwapActivity::from_vmstat(&VmStat::new())
+    }
+
+    /// Pages per second swapped in and out between an earlier sample and this one taken
+    /// `secs` apart, sustained swap in is the sign of a system short on memory
+    pub fn rate(&self, prev: &SwapActivity, secs: f64) -> (f64, f64) {
+        if secs <= 0.0 {
+            return (0.0, 0.0);
+        }
+        (
+            self.pswpin.saturating_sub(prev.pswpin) as f64 / secs,
+            self.pswpout.saturating_sub(prev.pswpout) as f64 / secs,
+        )
+    }
+
+    fn from_vmstat(vmstat: &VmStat) -> SwapActivity {
+        SwapActivity {
+            pswpin: vmstat.get_pswpin(),
+            pswpout: vmstat.get_pswpout(),
+        }
+    }
+}
+
+/// Swap used by a single process, from VmSwap in its status file
+#[derive(Debug)]
+pub struct ProcessSwap {
+    pid: String,
+    comm: String,
+    swap: u64,
+}
+
+impl PartialEq for ProcessSwap {
+    fn eq(&self, other: &ProcessSwap) -> bool {
+        (self.pid == other.pid) & (self.comm == other.comm) & (self.swap == other.swap)
+    }
+}
+
+impl fmt::Display for ProcessSwap {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmtr, "PID: {} {} Swap: {}", self.pid, self.comm, self.swap)
+    }
+}
+
+impl ProcessSwap {
+    pub fn get_pid(&self) -> String {
+        self.pid.clone()
+    }
+
+    pub fn get_comm(&self) -> String {
+        self.comm.clone()
+    }
+
+    /// Swapped out anonymous memory in kB
+    pub fn get_swap(&self) -> u64 {
+        self.swap
+    }
+
+    /// Every process with something swapped out, largest first. Processes which exit
+    /// while the list is being built are skipped
+    pub fn all() -> Vec<ProcessSwap> {
+        let mut procs: Vec<ProcessSwap> = all_status()
+            .into_iter()
+            .map(|(pid, status)| ProcessSwap::parse(pid, &status))
+            .filter(|p| p.swap > 0)
+            .collect();
+        procs.sort_by_key(|p| Reverse(p.swap));
+        procs
+    }
+
+    /// Swap used by PID in kB
+    pub fn pid(pid: &str) -> Result<ProcessSwap, io::Error> {
+        let status = fs::read_to_string(format!("{}/{}/status", PROC, pid))?;
+        Ok(ProcessSwap::parse(pid.to_string(), &status))
+    }
+
+    fn parse(pid: String, status: &str) -> ProcessSwap {
+        ProcessSwap {
+            pid,
+            comm: status_name(status),
+            swap: status_kb(status, "VmSwap"),
+        }
+    }
+}
+
+#[test]
+fn test_swaps_parse() {
+    let data = b"Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority
+/dev/dm-1                               partition\t8388604\t\t77568\t\t-2
+/var/swap\\040file                       file\t\t2097148\t\t0\t\t10
+";
+    let swaps = Swaps::parse(data);
+    assert_eq!(swaps.total(), 10485752);
+    assert_eq!(swaps.used(), 77568);
+    let devices = swaps.get_devices();
+    assert_eq!(
+        devices[0],
+        SwapDevice {
+            filename: PathBuf::from("/dev/dm-1"),
+            swap_type: "partition".to_string(),
+            size: 8388604,
+            used: 77568,
+            priority: -2,
+        }
+    );
+    assert_eq!(devices[1].get_filename(), Path::new("/var/swap file"));
+    assert_eq!(devices[1].get_priority(), 10);
+
+    let prev = SwapActivity::from_vmstat(&VmStat::parse("pgpgout 10\npswpin 100\npswpout 300\n"));
+    let now = SwapActivity::from_vmstat(&VmStat::parse("pgpgout 10\npswpin 150\npswpout 700\n"));
+    assert_eq!(now.rate(&prev, 2.0), (25.0, 200.0));
+
+    let status = "Name:\tWeb Content\nState:\tS (sleeping)\nVmSwap:\t   20480 kB\n";
+    assert_eq!(
+        ProcessSwap::parse("4242".to_string(), status),
+        ProcessSwap {
+            pid: "4242".to_string(),
+            comm: "Web Content".to_string(),
+            swap: 20480,
+        }
+    );
+}
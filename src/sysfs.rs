@@ -0,0 +1,42 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Reads an attribute file with the trailing newline removed
+pub(crate) fn read_attr(dir: &Path, attr: &str) -> Result<String, io::Error> {
+    fs::read_to_string(dir.join(attr)).map(|s| s.trim().to_string())
+}
+
+/// Reads a numeric attribute, missing or unparseable attributes are 0
+pub(crate) fn read_num(dir: &Path, attr: &str) -> u64 {
+    read_attr(dir, attr)
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(0)
+}
+
+/// Reads the leading number of an attribute such as SLUB's "objects", which is followed
+/// by per node counts. Unlike read_num errors are passed on, so a caller can tell an
+/// attribute it may not read from one that is 0
+pub(crate) fn read_first_num(dir: &Path, attr: &str) -> Result<u64, io::Error> {
+    let s = read_attr(dir, attr)?;
+    s.split_whitespace()
+        .next()
+        .and_then(|n| n.parse::<u64>().ok())
+        .ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{}: not a number", attr))
+        })
+}
+
+/// Sorted names of the entries in a directory, empty when it can't be read
+pub(crate) fn list_dir(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .collect(),
+        Err(_) => vec![],
+    };
+    names.sort();
+    names
+}
@@ -0,0 +1,59 @@
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// A scratch directory standing in for /sys, /dev and the like in tests. It is removed
+/// when dropped, so it goes away even when an assert fails
+pub struct TestTree {
+    root: PathBuf,
+}
+
+impl TestTree {
+    /// Creates linmon-<name>-<pid> under the temp dir, clearing any left by a killed run
+    pub fn new(name: &str) -> TestTree {
+        let root = ::std::env::temp_dir().join(format!("linmon-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        TestTree { root }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn join(&self, path: &str) -> PathBuf {
+        self.root.join(path)
+    }
+
+    pub fn mkdir(&self, path: &str) {
+        fs::create_dir_all(self.join(path)).unwrap();
+    }
+
+    /// Writes a file, creating its parent directories
+    pub fn write<C: AsRef<[u8]>>(&self, path: &str, contents: C) {
+        let path = self.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    /// Writes each path and contents pair, e.g. a set of sysfs attributes
+    pub fn write_all(&self, files: &[(&str, &str)]) {
+        for &(path, contents) in files {
+            self.write(path, contents);
+        }
+    }
+
+    /// Creates a symlink, creating its parent directories
+    pub fn symlink(&self, target: &str, path: &str) {
+        let path = self.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        symlink(target, path).unwrap();
+    }
+}
+
+impl Drop for TestTree {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
@@ -1,7 +1,12 @@
 use std::fs::File;
 use std::path::Path;
 use std::io::Read;
+use std::io;
 use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use libc;
+use cpu;
+use stat::Stat;
 
 pub static UPTIME: &str = "/proc/uptime";
 
@@ -9,24 +14,118 @@ pub static UPTIME: &str = "/proc/uptime";
 pub struct UpTime {
     uptime: f64,
     idle: f64,
+    btime: u64,
+    boottime: f64,
+    monotonic: f64,
 }
 
 impl PartialEq for UpTime {
     fn eq(&self, other: &UpTime) -> bool {
-        (self.uptime == other.uptime) & (self.idle == other.idle)
+        (self.uptime == other.uptime) & (self.idle == other.idle) & (self.btime == other.btime)
+            & (self.boottime == other.boottime) & (self.monotonic == other.monotonic)
     }
 }
 
 impl fmt::Display for UpTime {
     fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmtr, "Uptime: {}", self.uptime)
+        write!(fmtr, "Uptime: {}", self.pretty())
     }
 }
 
 impl UpTime {
+    /// Samples /proc/uptime along with btime from /proc/stat and the boot and monotonic
+    /// clocks to cross-check it against
     pub fn new() -> UpTime {
+        // read monotonic first so the gap between the reads can't make boottime smaller
+        let monotonic = UpTime::clock(libc::CLOCK_MONOTONIC).unwrap_or_default();
         let text = UpTime::fetch();
-        UpTime::parse(text)
+        let boottime = UpTime::clock(libc::CLOCK_BOOTTIME).unwrap_or_default();
+        UpTime::with_clocks(text, Stat::new().get_btime(), boottime, monotonic)
+    }
+
+    /// Seconds since boot, including time spent suspended
+    pub fn get_uptime(&self) -> f64 {
+        self.uptime
+    }
+
+    /// Seconds spent idle summed across every CPU
+    pub fn get_idle(&self) -> f64 {
+        self.idle
+    }
+
+    /// When the system booted, from btime in /proc/stat. It is fixed when sampled so it
+    /// doesn't drift with the age of the sample
+    pub fn boot_time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.btime)
+    }
+
+    /// Seconds spent suspended since boot, the difference between CLOCK_BOOTTIME which
+    /// keeps counting while suspended and CLOCK_MONOTONIC which stops
+    pub fn get_suspended(&self) -> f64 {
+        (self.boottime - self.monotonic).max(0.0)
+    }
+
+    /// Whether the uptime includes time spent suspended, which it does when it is closer
+    /// to CLOCK_BOOTTIME than CLOCK_MONOTONIC. Old kernels reported monotonic time. The
+    /// clocks agree to within the uptime's 10ms resolution until the system suspends
+    pub fn includes_suspend(&self) -> bool {
+        let suspended = self.get_suspended();
+        (suspended < 1.0) | ((self.uptime - self.boottime).abs() < suspended / 2.0)
+    }
+
+    /// Seconds the system has been running since boot, leaving out time suspended
+    pub fn awake(&self) -> f64 {
+        if self.includes_suspend() {
+            (self.uptime - self.get_suspended()).max(0.0)
+        } else {
+            self.uptime
+        }
+    }
+
+    /// Percentage of time idle since boot, using the online CPU count
+    pub fn idle_percent(&self) -> f64 {
+        self.idle_percent_for(cpu::online_cpus())
+    }
+
+    /// Percentage of time idle since boot given the number of CPUs idle time was summed
+    /// over. CPUs don't count idle time while suspended so that is left out
+    pub fn idle_percent_for(&self, cpus: usize) -> f64 {
+        let awake = self.awake();
+        if awake == 0.0 {
+            return 0.0;
+        }
+        self.idle / (awake * cpus.max(1) as f64) * 100.0
+    }
+
+    /// Formats the uptime the way uptime(1) does, e.g. "up 12 days, 3:04" or "up 5 min"
+    pub fn pretty(&self) -> String {
+        let total = self.uptime as u64;
+        let days = total / 86400;
+        let hours = (total % 86400) / 3600;
+        let mins = (total % 3600) / 60;
+        let mut s = "up ".to_string();
+        match days {
+            0 => {}
+            1 => s.push_str("1 day, "),
+            d => s.push_str(&format!("{} days, ", d)),
+        }
+        if hours > 0 {
+            s.push_str(&format!("{}:{:02}", hours, mins));
+        } else {
+            s.push_str(&format!("{} min", mins));
+        }
+        s
+    }
+
+    fn clock(id: libc::clockid_t) -> Result<Duration, io::Error> {
+        let mut ts = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        if unsafe { libc::clock_gettime(id, &mut ts) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32))
     }
 
     fn fetch() -> String {
@@ -37,12 +136,23 @@ impl UpTime {
         data
     }
 
+    fn with_clocks(up: String, btime: u64, boottime: Duration, monotonic: Duration) -> UpTime {
+        let mut uptime = UpTime::parse(up);
+        uptime.btime = btime;
+        uptime.boottime = boottime.as_secs_f64();
+        uptime.monotonic = monotonic.as_secs_f64();
+        uptime
+    }
+
     fn parse(up: String) -> UpTime {
         let mut parse: Vec<&str> = up.split(' ').collect();
         parse.retain(|&i| i != "");
         UpTime {
             uptime: parse[0].parse::<f64>().unwrap(),
-            idle: parse[1].parse::<f64>().unwrap(),
+            idle: parse[1].trim().parse::<f64>().unwrap(),
+            btime: 0,
+            boottime: 0.0,
+            monotonic: 0.0,
         }
     }
 }
@@ -53,7 +163,52 @@ fn test_uptime_parse() {
     let u = UpTime {
         uptime: 1650431.01,
         idle: 1696373.78,
+        btime: 0,
+        boottime: 0.0,
+        monotonic: 0.0,
     };
     let test = UpTime::parse(uptime.to_string());
     assert_eq!(test, u)
 }
+
+#[test]
+fn test_uptime_pretty() {
+    let up = UpTime::parse("1048022.00 3812345.12\n".to_string());
+    assert_eq!(up.pretty(), "up 12 days, 3:07");
+    assert_eq!(format!("{:.2}", up.idle_percent_for(4)), "90.94");
+    let up = UpTime::parse("300.00 290.00\n".to_string());
+    assert_eq!(up.pretty(), "up 5 min");
+    let up = UpTime::parse("90000.00 0.00\n".to_string());
+    assert_eq!(up.pretty(), "up 1 day, 1:00")
+}
+
+#[test]
+fn test_uptime_boottime() {
+    let mut up = UpTime::with_clocks(
+        "1000.00 2400.00\n".to_string(),
+        1062191376,
+        Duration::from_millis(1000200),
+        Duration::from_millis(700200),
+    );
+    assert_eq!(up.boot_time(), UNIX_EPOCH + Duration::from_secs(1062191376));
+    assert_eq!(up.get_suspended(), 300.0);
+    assert!(up.includes_suspend());
+    assert_eq!(up.awake(), 700.0);
+    assert_eq!(format!("{:.2}", up.idle_percent_for(4)), "85.71");
+
+    // kernels which reported monotonic time in /proc/uptime
+    up.uptime = 700.1;
+    assert!(!up.includes_suspend());
+    assert_eq!(up.awake(), 700.1);
+
+    // never suspended, the clocks agree and uptime trails them by its resolution
+    let up = UpTime::with_clocks(
+        "700.19 2000.00\n".to_string(),
+        1062191376,
+        Duration::from_millis(700200),
+        Duration::from_millis(700200),
+    );
+    assert_eq!(up.get_suspended(), 0.0);
+    assert!(up.includes_suspend());
+    assert_eq!(up.awake(), 700.19)
+}
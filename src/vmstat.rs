@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use std::io::Read;
+use std::fmt;
+
+pub static VMSTAT: &str = "/proc/vmstat";
+
+/// Older kernels split some counters per zone, e.g. pgscan_kswapd_normal
+static ZONES: &[&str] = &["dma", "dma32", "normal", "movable", "high", "device"];
+
+/// Keys starting with nr_ that count events rather than report a current level
+static NR_COUNTERS: &[&str] = &[
+    "nr_dirtied",
+    "nr_written",
+    "nr_foll_pin_acquired",
+    "nr_foll_pin_released",
+];
+
+/// Virtual memory event counters since boot, in pages or events. The nr_* keys left in
+/// the map are gauges rather than counters
+#[derive(Debug)]
+pub struct VmStat {
+    pgpgin: u64,
+    pgpgout: u64,
+    pswpin: u64,
+    pswpout: u64,
+    pgfault: u64,
+    pgmajfault: u64,
+    pgfree: u64,
+    pgscan_kswapd: u64,
+    pgscan_direct: u64,
+    pgsteal_kswapd: u64,
+    pgsteal_direct: u64,
+    allocstall: u64,
+    oom_kill: u64,
+    thp_fault_alloc: u64,
+    thp_fault_fallback: u64,
+    thp_collapse_alloc: u64,
+    thp_split_page: u64,
+    compact_stall: u64,
+    compact_fail: u64,
+    compact_success: u64,
+    numa_hit: u64,
+    numa_miss: u64,
+    numa_foreign: u64,
+    numa_local: u64,
+    numa_other: u64,
+    numa_pages_migrated: u64,
+    other: HashMap<String, u64>,
+}
+
+impl PartialEq for VmStat {
+    fn eq(&self, other: &VmStat) -> bool {
+        (self.pgpgin == other.pgpgin) & (self.pgpgout == other.pgpgout)
+            & (self.pswpin == other.pswpin) & (self.pswpout == other.pswpout)
+            & (self.pgfault == other.pgfault) & (self.pgmajfault == other.pgmajfault)
+            & (self.pgfree == other.pgfree) & (self.pgscan_kswapd == other.pgscan_kswapd)
+            & (self.pgscan_direct == other.pgscan_direct)
+            & (self.pgsteal_kswapd == other.pgsteal_kswapd)
+            & (self.pgsteal_direct == other.pgsteal_direct)
+            & (self.allocstall == other.allocstall) & (self.oom_kill == other.oom_kill)
+            & (self.thp_fault_alloc == other.thp_fault_alloc)
+            & (self.thp_fault_fallback == other.thp_fault_fallback)
+            & (self.thp_collapse_alloc == other.thp_collapse_alloc)
+            & (self.thp_split_page == other.thp_split_page)
+            & (self.compact_stall == other.compact_stall)
+            & (self.compact_fail == other.compact_fail)
+            & (self.compact_success == other.compact_success)
+            & (self.numa_hit == other.numa_hit) & (self.numa_miss == other.numa_miss)
+            & (self.numa_foreign == other.numa_foreign) & (self.numa_local == other.numa_local)
+            & (self.numa_other == other.numa_other)
+            & (self.numa_pages_migrated == other.numa_pages_migrated)
+            & (self.other == other.other)
+    }
+}
+
+impl fmt::Display for VmStat {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "Faults: {} Major: {} Scanned: {} Stolen: {} Swap in/out: {}/{} OOM kills: {}",
+            self.pgfault,
+            self.pgmajfault,
+            self.pgscan(),
+            self.pgsteal(),
+            self.pswpin,
+            self.pswpout,
+            self.oom_kill
+        )
+    }
+}
+
+impl VmStat {
+    pub fn new() -> VmStat {
+        let text = VmStat::fetch();
+        VmStat::parse(&text)
+    }
+
+    /// Pages read in from block devices
+    pub fn get_pgpgin(&self) -> u64 {
+        self.pgpgin
+    }
+
+    pub fn get_pgpgout(&self) -> u64 {
+        self.pgpgout
+    }
+
+    /// Pages swapped in
+    pub fn get_pswpin(&self) -> u64 {
+        self.pswpin
+    }
+
+    pub fn get_pswpout(&self) -> u64 {
+        self.pswpout
+    }
+
+    pub fn get_pgfault(&self) -> u64 {
+        self.pgfault
+    }
+
+    /// Faults which had to wait for I/O
+    pub fn get_pgmajfault(&self) -> u64 {
+        self.pgmajfault
+    }
+
+    pub fn get_pgfree(&self) -> u64 {
+        self.pgfree
+    }
+
+    /// Pages scanned by kswapd in the background
+    pub fn get_pgscan_kswapd(&self) -> u64 {
+        self.pgscan_kswapd
+    }
+
+    /// Pages scanned by allocating tasks that had to reclaim themselves
+    pub fn get_pgscan_direct(&self) -> u64 {
+        self.pgscan_direct
+    }
+
+    pub fn get_pgsteal_kswapd(&self) -> u64 {
+        self.pgsteal_kswapd
+    }
+
+    pub fn get_pgsteal_direct(&self) -> u64 {
+        self.pgsteal_direct
+    }
+
+    /// Times an allocation stalled for direct reclaim, summed over zones
+    pub fn get_allocstall(&self) -> u64 {
+        self.allocstall
+    }
+
+    /// OOM killer invocations, Linux 4.13 and later
+    pub fn get_oom_kill(&self) -> u64 {
+        self.oom_kill
+    }
+
+    pub fn get_thp_fault_alloc(&self) -> u64 {
+        self.thp_fault_alloc
+    }
+
+    /// Faults which wanted a huge page but fell back to small pages
+    pub fn get_thp_fault_fallback(&self) -> u64 {
+        self.thp_fault_fallback
+    }
+
+    pub fn get_thp_collapse_alloc(&self) -> u64 {
+        self.thp_collapse_alloc
+    }
+
+    pub fn get_thp_split_page(&self) -> u64 {
+        self.thp_split_page
+    }
+
+    /// Allocations which stalled for direct compaction
+    pub fn get_compact_stall(&self) -> u64 {
+        self.compact_stall
+    }
+
+    pub fn get_compact_fail(&self) -> u64 {
+        self.compact_fail
+    }
+
+    pub fn get_compact_success(&self) -> u64 {
+        self.compact_success
+    }
+
+    pub fn get_numa_hit(&self) -> u64 {
+        self.numa_hit
+    }
+
+    pub fn get_numa_miss(&self) -> u64 {
+        self.numa_miss
+    }
+
+    pub fn get_numa_foreign(&self) -> u64 {
+        self.numa_foreign
+    }
+
+    pub fn get_numa_local(&self) -> u64 {
+        self.numa_local
+    }
+
+    pub fn get_numa_other(&self) -> u64 {
+        self.numa_other
+    }
+
+    pub fn get_numa_pages_migrated(&self) -> u64 {
+        self.numa_pages_migrated
+    }
+
+    /// Any key without a dedicated field, e.g. "nr_dirty" or "workingset_refault_file"
+    pub fn get(&self, key: &str) -> Option<u64> {
+        self.other.get(key).cloned()
+    }
+
+    pub fn get_other(&self) -> &HashMap<String, u64> {
+        &self.other
+    }
+
+    /// Pages scanned for reclaim by kswapd and direct reclaim
+    pub fn pgscan(&self) -> u64 {
+        self.pgscan_kswapd + self.pgscan_direct
+    }
+
+    /// Pages reclaimed by kswapd and direct reclaim
+    pub fn pgsteal(&self) -> u64 {
+        self.pgsteal_kswapd + self.pgsteal_direct
+    }
+
+    /// Percentage of scanned pages that were reclaimed. On a delta, a low value with a
+    /// high scan rate is a reclaim storm: the kernel is working hard to find memory
+    pub fn reclaim_efficiency(&self) -> f64 {
+        let scanned = self.pgscan();
+        if scanned == 0 {
+            return 100.0;
+        }
+        self.pgsteal() as f64 / scanned as f64 * 100.0
+    }
+
+    /// Counters accumulated since an earlier sample, divide by the seconds between the
+    /// samples for rates. Keys missing from the earlier sample count from 0. Gauges such
+    /// as nr_dirty or nr_free_pages keep their current value
+    pub fn delta(&self, prev: &VmStat) -> VmStat {
+        let d = |now: u64, then: u64| now.saturating_sub(then);
+        VmStat {
+            pgpgin: d(self.pgpgin, prev.pgpgin),
+            pgpgout: d(self.pgpgout, prev.pgpgout),
+            pswpin: d(self.pswpin, prev.pswpin),
+            pswpout: d(self.pswpout, prev.pswpout),
+            pgfault: d(self.pgfault, prev.pgfault),
+            pgmajfault: d(self.pgmajfault, prev.pgmajfault),
+            pgfree: d(self.pgfree, prev.pgfree),
+            pgscan_kswapd: d(self.pgscan_kswapd, prev.pgscan_kswapd),
+            pgscan_direct: d(self.pgscan_direct, prev.pgscan_direct),
+            pgsteal_kswapd: d(self.pgsteal_kswapd, prev.pgsteal_kswapd),
+            pgsteal_direct: d(self.pgsteal_direct, prev.pgsteal_direct),
+            allocstall: d(self.allocstall, prev.allocstall),
+            oom_kill: d(self.oom_kill, prev.oom_kill),
+            thp_fault_alloc: d(self.thp_fault_alloc, prev.thp_fault_alloc),
+            thp_fault_fallback: d(self.thp_fault_fallback, prev.thp_fault_fallback),
+            thp_collapse_alloc: d(self.thp_collapse_alloc, prev.thp_collapse_alloc),
+            thp_split_page: d(self.thp_split_page, prev.thp_split_page),
+            compact_stall: d(self.compact_stall, prev.compact_stall),
+            compact_fail: d(self.compact_fail, prev.compact_fail),
+            compact_success: d(self.compact_success, prev.compact_success),
+            numa_hit: d(self.numa_hit, prev.numa_hit),
+            numa_miss: d(self.numa_miss, prev.numa_miss),
+            numa_foreign: d(self.numa_foreign, prev.numa_foreign),
+            numa_local: d(self.numa_local, prev.numa_local),
+            numa_other: d(self.numa_other, prev.numa_other),
+            numa_pages_migrated: d(self.numa_pages_migrated, prev.numa_pages_migrated),
+            other: self.other
+                .iter()
+                .map(|(k, v)| if is_gauge(k) {
+                    (k.clone(), *v)
+                } else {
+                    (k.clone(), d(*v, prev.get(k).unwrap_or(0)))
+                })
+                .collect(),
+        }
+    }
+
+    fn fetch() -> String {
+        let path = Path::new(VMSTAT);
+        let mut data = String::new();
+        let mut f = File::open(path).expect("Unable to open file");
+        f.read_to_string(&mut data).expect("Unable to read string");
+        data
+    }
+
+    pub(crate) fn parse(s: &str) -> VmStat {
+        let mut values: HashMap<String, u64> = HashMap::new();
+        for line in s.lines() {
+            let split: Vec<&str> = line.split_whitespace().collect();
+            if split.len() < 2 {
+                continue;
+            }
+            values.insert(split[0].to_string(), split[1].parse::<u64>().unwrap_or(0));
+        }
+        let pgscan_kswapd = take_zoned(&mut values, "pgscan_kswapd");
+        let pgscan_direct = take_zoned(&mut values, "pgscan_direct");
+        let pgsteal_kswapd = take_zoned(&mut values, "pgsteal_kswapd");
+        let pgsteal_direct = take_zoned(&mut values, "pgsteal_direct");
+        let allocstall = take_zoned(&mut values, "allocstall");
+        let mut take = |key: &str| values.remove(key).unwrap_or(0);
+        VmStat {
+            pgpgin: take("pgpgin"),
+            pgpgout: take("pgpgout"),
+            pswpin: take("pswpin"),
+            pswpout: take("pswpout"),
+            pgfault: take("pgfault"),
+            pgmajfault: take("pgmajfault"),
+            pgfree: take("pgfree"),
+            pgscan_kswapd,
+            pgscan_direct,
+            pgsteal_kswapd,
+            pgsteal_direct,
+            allocstall,
+            oom_kill: take("oom_kill"),
+            thp_fault_alloc: take("thp_fault_alloc"),
+            thp_fault_fallback: take("thp_fault_fallback"),
+            thp_collapse_alloc: take("thp_collapse_alloc"),
+            thp_split_page: take("thp_split_page"),
+            compact_stall: take("compact_stall"),
+            compact_fail: take("compact_fail"),
+            compact_success: take("compact_success"),
+            numa_hit: take("numa_hit"),
+            numa_miss: take("numa_miss"),
+            numa_foreign: take("numa_foreign"),
+            numa_local: take("numa_local"),
+            numa_other: take("numa_other"),
+            numa_pages_migrated: take("numa_pages_migrated"),
+            other: values,
+        }
+    }
+}
+
+/// A counter which newer kernels report once and older ones per zone
+fn take_zoned(values: &mut HashMap<String, u64>, key: &str) -> u64 {
+    if let Some(v) = values.remove(key) {
+        return v;
+    }
+    ZONES
+        .iter()
+        .filter_map(|z| values.remove(&format!("{}_{}", key, z)))
+        .sum()
+}
+
+/// Levels rather than event counts, they can fall between samples
+fn is_gauge(key: &str) -> bool {
+    (key.starts_with("nr_") & !NR_COUNTERS.contains(&key)) | (key == "workingset_nodes")
+}
+
+#[test]
+fn test_vmstat_parse() {
+    let prev = VmStat::parse(
+        "nr_free_pages 1000
+nr_dirty 100
+nr_dirtied 500
+pgfault 5000
+pgmajfault 10
+pgscan_kswapd 0
+pgscan_direct 0
+pgscan_direct_throttle 0
+pgsteal_kswapd 0
+pgsteal_direct 0
+allocstall_normal 1
+allocstall_movable 2
+oom_kill 0
+thp_fault_alloc 7
+",
+    );
+    assert_eq!(prev.get_allocstall(), 3);
+    assert_eq!(prev.get("pgscan_direct_throttle"), Some(0));
+    assert_eq!(prev.get("nr_dirty"), Some(100));
+    assert_eq!(prev.reclaim_efficiency(), 100.0);
+
+    // 3.x kernels split reclaim counters per zone
+    let now = VmStat::parse(
+        "nr_free_pages 800
+nr_dirty 20
+nr_dirtied 700
+pgfault 9000
+pgmajfault 510
+pgscan_kswapd_dma32 1000
+pgscan_kswapd_normal 9000
+pgscan_direct_normal 10000
+pgsteal_kswapd_normal 4000
+pgsteal_direct_normal 1000
+allocstall 8
+oom_kill 1
+thp_fault_alloc 7
+",
+    );
+    let delta = now.delta(&prev);
+    assert_eq!(delta.get_pgfault(), 4000);
+    assert_eq!(delta.get_pgmajfault(), 500);
+    assert_eq!(delta.pgscan(), 20000);
+    assert_eq!(delta.reclaim_efficiency(), 25.0);
+    assert_eq!(delta.get_allocstall(), 5);
+    assert_eq!(delta.get_oom_kill(), 1);
+    // gauges carry their current value, even when they fell
+    assert_eq!(delta.get("nr_dirty"), Some(20));
+    assert_eq!(delta.get("nr_free_pages"), Some(800));
+    assert_eq!(delta.get("nr_dirtied"), Some(200));
+}
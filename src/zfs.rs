@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+use mounts::Mount;
+
+pub static SPL_KSTAT_ZFS: &str = "/proc/spl/kstat/zfs";
+pub static ARCSTATS: &str = "/proc/spl/kstat/zfs/arcstats";
+
+/// ZFS adaptive replacement cache statistics. ARC memory shows up as used rather than
+/// cached in meminfo, so this is needed to explain memory use on ZFS hosts
+#[derive(Debug)]
+pub struct ArcStats {
+    stats: HashMap<String, u64>,
+}
+
+impl PartialEq for ArcStats {
+    fn eq(&self, other: &ArcStats) -> bool {
+        self.stats == other.stats
+    }
+}
+
+impl fmt::Display for ArcStats {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "ARC Size: {} Target: {} Max: {} Hit%: {:.1}",
+            self.size(),
+            self.target(),
+            self.max(),
+            self.hit_percent()
+        )
+    }
+}
+
+impl ArcStats {
+    pub fn get_stats(&self) -> &HashMap<String, u64> {
+        &self.stats
+    }
+
+    pub fn new() -> ArcStats {
+        let mut data = String::new();
+        let mut f = File::open(ARCSTATS).expect("Unable to open file");
+        f.read_to_string(&mut data).expect("Unable to read string");
+        ArcStats::parse(&data)
+    }
+
+    /// Any counter by its kstat name, 0 for names this ZFS version doesn't have
+    pub fn get(&self, name: &str) -> u64 {
+        self.stats.get(name).cloned().unwrap_or(0)
+    }
+
+    /// Current size in bytes
+    pub fn size(&self) -> u64 {
+        self.get("size")
+    }
+
+    /// Size the ARC is currently aiming for
+    pub fn target(&self) -> u64 {
+        self.get("c")
+    }
+
+    pub fn min(&self) -> u64 {
+        self.get("c_min")
+    }
+
+    pub fn max(&self) -> u64 {
+        self.get("c_max")
+    }
+
+    pub fn hit_percent(&self) -> f64 {
+        let hits = self.get("hits");
+        let total = hits + self.get("misses");
+        if total == 0 {
+            return 0.0;
+        }
+        hits as f64 / total as f64 * 100.0
+    }
+
+    /// The first line is the kstat header and the second names the columns, after that
+    /// every line is name, type, value
+    fn parse(s: &str) -> ArcStats {
+        let mut stats = HashMap::new();
+        for line in s.lines().skip(2) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 3 {
+                continue;
+            }
+            if let Ok(v) = fields[2].parse::<u64>() {
+                stats.insert(fields[0].to_string(), v);
+            }
+        }
+        ArcStats { stats }
+    }
+}
+
+/// State of an imported pool
+#[derive(Debug)]
+pub struct ZfsPool {
+    name: String,
+    state: String,
+}
+
+impl PartialEq for ZfsPool {
+    fn eq(&self, other: &ZfsPool) -> bool {
+        (self.name == other.name) & (self.state == other.state)
+    }
+}
+
+impl fmt::Display for ZfsPool {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmtr, "Pool: {} State: {}", self.name, self.state)
+    }
+}
+
+impl ZfsPool {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// ONLINE, DEGRADED, FAULTED, OFFLINE, UNAVAIL or REMOVED
+    pub fn get_state(&self) -> &str {
+        &self.state
+    }
+
+    pub fn is_online(&self) -> bool {
+        self.state == "ONLINE"
+    }
+
+    pub fn new(name: &str) -> Result<ZfsPool, io::Error> {
+        ZfsPool::read(Path::new(SPL_KSTAT_ZFS), name)
+    }
+
+    /// The pool a mounted dataset lives in, datasets are mounted with pool/dataset as
+    /// their source and snapshots with pool@snap or pool/dataset@snap
+    pub fn for_mount(mount: &Mount) -> Option<ZfsPool> {
+        if mount.get_fs_type() != "zfs" {
+            return None;
+        }
+        ZfsPool::new(pool_name(&mount.get_dev().to_string_lossy())).ok()
+    }
+
+    /// Every imported pool, each has a directory under kstat/zfs with a state file
+    pub fn all() -> Vec<ZfsPool> {
+        let root = Path::new(SPL_KSTAT_ZFS);
+        let mut pools: Vec<ZfsPool> = match fs::read_dir(root) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| ZfsPool::read(root, &e.file_name().to_string_lossy()).ok())
+                .collect(),
+            Err(_) => vec![],
+        };
+        pools.sort_by(|a, b| a.name.cmp(&b.name));
+        pools
+    }
+
+    fn read(root: &Path, name: &str) -> Result<ZfsPool, io::Error> {
+        let state = fs::read_to_string(root.join(name).join("state"))?;
+        Ok(ZfsPool {
+            name: name.to_string(),
+            state: state.trim().to_string(),
+        })
+    }
+}
+
+/// Pool part of a mount source, the dataset path and any snapshot name are dropped
+fn pool_name(dev: &str) -> &str {
+    dev.split(['/', '@']).next().unwrap_or("")
+}
+
+#[test]
+fn test_zfs_parse() {
+    use testtree::TestTree;
+
+    let arcstats = "13 1 0x01 96 26112 16512407563 2297871289487617
+name                            type data
+hits                            4    9000
+misses                          4    1000
+c                               4    4294967296
+c_min                           4    1073741824
+c_max                           4    8589934592
+size                            4    4000000000
+";
+    let arc = ArcStats::parse(arcstats);
+    assert_eq!(arc.size(), 4000000000);
+    assert_eq!(arc.max(), 8589934592);
+    assert_eq!(arc.hit_percent(), 90.0);
+    assert_eq!(arc.get("l2_size"), 0);
+
+    let tree = TestTree::new("zfs");
+    tree.write("tank/state", "DEGRADED\n");
+    let pool = ZfsPool::read(tree.path(), "tank").unwrap();
+    assert_eq!(pool.get_state(), "DEGRADED");
+    assert!(!pool.is_online());
+    assert!(ZfsPool::read(tree.path(), "missing").is_err());
+
+    assert_eq!(pool_name("tank/home/alice"), "tank");
+    assert_eq!(pool_name("tank@daily"), "tank");
+    assert_eq!(pool_name("tank/home@daily"), "tank");
+}